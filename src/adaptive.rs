@@ -0,0 +1,38 @@
+use cgmath::Vector3;
+
+/// Pixels darker than this are judged by their error relative to it instead, so that nearly black
+/// pixels don't soak up samples chasing a tiny mean.
+const DARK: f64 = 0.01;
+
+/// Settings for spending more samples on noisier pixels.
+pub struct Adaptive {
+    /// Largest acceptable standard error of a pixel's mean luminance, relative to that mean.
+    pub threshold: f64,
+    pub max_samples: u32,
+}
+
+impl Adaptive {
+    /// Whether a pixel with `count` samples adding up to `sum`, whose luminances squared add up
+    /// to `sum_squares`, should get more.
+    pub fn needs_more(&self, sum: Vector3<f64>, sum_squares: f64, count: u32) -> bool {
+        count < self.max_samples && relative_error(sum, sum_squares, count) > self.threshold
+    }
+}
+
+/// Estimated standard error of the mean luminance of a pixel's samples, relative to the mean
+/// itself. Infinite with too few samples to tell.
+fn relative_error(sum: Vector3<f64>, sum_squares: f64, count: u32) -> f64 {
+    if count < 2 {
+        return f64::INFINITY;
+    }
+    let n = f64::from(count);
+    let mean = luminance(sum) / n;
+    // Rounding can take this a hair below zero for pixels that are all the same
+    let variance = ((sum_squares - n * mean * mean) / (n - 1.0)).max(0.0);
+    (variance / n).sqrt() / mean.abs().max(DARK)
+}
+
+/// As `Vec3::luminance`, for sums of samples.
+fn luminance(c: Vector3<f64>) -> f64 {
+    0.2126 * c.x + 0.7152 * c.y + 0.0722 * c.z
+}
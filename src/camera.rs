@@ -1,6 +1,7 @@
 use cgmath::{Angle, Deg, Rad};
+use rand::Rng;
 
-use crate::{ray::Ray, Vec3};
+use crate::{math::Float, ray::Ray, Vec3};
 
 pub struct Camera {
     origin: Vec3,
@@ -11,22 +12,51 @@ pub struct Camera {
     w: Vec3,
     u: Vec3,
     v: Vec3,
-    lens_radius: f64,
-    shutter_open_time: f64,
-    shutter_close_time: f64,
+    lens_radius: Float,
+    shutter_open_time: Float,
+    shutter_close_time: Float,
+}
+
+/// Everything about a camera except the aspect ratio, which comes from the image being rendered.
+pub struct CameraSettings {
+    pub position: Vec3,
+    pub target: Vec3,
+    pub vert: Vec3,
+    pub vfov: Deg<Float>,
+    pub aperture: Float,
+    pub focus_dist: Float,
+    pub shutter_open_time: Float,
+    pub shutter_close_time: Float,
+}
+
+impl CameraSettings {
+    pub fn build(&self, aspect_ratio: Float) -> Camera {
+        Camera::new(
+            self.position,
+            self.target,
+            self.vert,
+            self.vfov,
+            aspect_ratio,
+            self.aperture,
+            self.focus_dist,
+            self.shutter_open_time,
+            self.shutter_close_time,
+        )
+    }
 }
 
 impl Camera {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         position: Vec3,
         target: Vec3,
         vert: Vec3,
-        vfov: Deg<f64>,
-        aspect_ratio: f64,
-        aperture: f64,
-        focus_dist: f64,
-        shutter_open_time: f64,
-        shutter_close_time: f64,
+        vfov: Deg<Float>,
+        aspect_ratio: Float,
+        aperture: Float,
+        focus_dist: Float,
+        shutter_open_time: Float,
+        shutter_close_time: Float,
     ) -> Self {
         let theta = Rad::from(vfov);
         let h = (theta / 2.0).tan();
@@ -41,7 +71,7 @@ impl Camera {
 
         let horizontal = focus_dist * viewport_width * u;
         let vertical = focus_dist * viewport_height * v;
-        let lower_left_corner = origin - horizontal / 2 - vertical / 2 - focus_dist * w;
+        let lower_left_corner = origin - horizontal / 2.0 - vertical / 2.0 - focus_dist * w;
 
         let lens_radius = aperture / 2.0;
 
@@ -60,8 +90,8 @@ impl Camera {
     }
 
     #[inline]
-    pub fn get_ray(&self, s: f64, t: f64) -> Ray {
-        let rd = self.lens_radius * Vec3::random_in_unit_disc();
+    pub fn get_ray(&self, s: Float, t: Float, rng: &mut impl Rng) -> Ray {
+        let rd = self.lens_radius * Vec3::random_in_unit_disc(rng);
         let offset = self.u * rd.x + self.v * rd.y;
 
         Ray {
@@ -69,10 +99,10 @@ impl Camera {
             direction: self.lower_left_corner + s * self.horizontal + t * self.vertical
                 - self.origin
                 - offset,
-            time: if (self.shutter_close_time - self.shutter_open_time).abs() < f64::EPSILON {
+            time: if (self.shutter_close_time - self.shutter_open_time).abs() < Float::EPSILON {
                 self.shutter_open_time
             } else {
-                crate::rand_f64!(self.shutter_open_time, self.shutter_close_time)
+                rng.gen_range(self.shutter_open_time..self.shutter_close_time)
             },
         }
     }
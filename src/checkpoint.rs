@@ -0,0 +1,183 @@
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use crate::{math::Float, Error, Framebuffer, Result};
+
+const MAGIC: &[u8; 8] = b"RTCKPT04";
+/// Bits in a `Float`. The same seed builds a different scene in each precision, so a render can
+/// only be carried on in the precision it was started in.
+const PRECISION: u32 = 8 * std::mem::size_of::<Float>() as u32;
+
+/// A render in progress, along with what's needed to render more samples of the same image.
+pub struct Checkpoint {
+    /// The built-in scene name or scene file path the render was started with.
+    pub scene: String,
+    /// The scene's `scene::fingerprint`, to tell whether it's changed since.
+    pub scene_hash: u64,
+    pub seed: u64,
+    pub max_depth: i32,
+    pub framebuffer: Framebuffer,
+}
+
+impl Checkpoint {
+    /// Writes to a temporary file next to `path` and then moves it into place, so that a
+    /// render killed part way through saving leaves the last checkpoint intact.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut temp = path.as_os_str().to_owned();
+        temp.push(".tmp");
+
+        self.write(Path::new(&temp))
+            .and_then(|()| std::fs::rename(&temp, path))
+            .map_err(|e| Error::Io(path.into(), e))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::read(path).map_err(|e| Error::Io(path.into(), e))?
+    }
+
+    fn write(&self, path: &Path) -> io::Result<()> {
+        let fb = &self.framebuffer;
+        let mut file = BufWriter::new(File::create(path)?);
+        file.write_all(MAGIC)?;
+        file.write_all(&PRECISION.to_le_bytes())?;
+        file.write_all(&(self.scene.len() as u32).to_le_bytes())?;
+        file.write_all(self.scene.as_bytes())?;
+        file.write_all(&self.scene_hash.to_le_bytes())?;
+        file.write_all(&self.seed.to_le_bytes())?;
+        file.write_all(&self.max_depth.to_le_bytes())?;
+        file.write_all(&fb.width().to_le_bytes())?;
+        file.write_all(&fb.height().to_le_bytes())?;
+        for ((sum, sum_squares), count) in fb.sums.iter().zip(&fb.sum_squares).zip(&fb.counts) {
+            for c in [sum.x, sum.y, sum.z, *sum_squares] {
+                file.write_all(&c.to_le_bytes())?;
+            }
+            file.write_all(&count.to_le_bytes())?;
+        }
+        file.into_inner()?.sync_all()
+    }
+
+    /// Fails on the inside if the file isn't a checkpoint this build can carry on.
+    fn read(path: &Path) -> io::Result<Result<Self>> {
+        let mut file = BufReader::new(File::open(path)?);
+
+        if read_array(&mut file)? != *MAGIC {
+            return Ok(Err(Error::NotACheckpoint(path.into())));
+        }
+        let precision = read_u32(&mut file)?;
+        if precision != PRECISION {
+            return Ok(Err(Error::OtherPrecision(path.into(), precision)));
+        }
+
+        let scene_len = read_u32(&mut file)? as usize;
+        let mut scene = vec![0; scene_len];
+        file.read_exact(&mut scene)?;
+        let scene =
+            String::from_utf8(scene).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let scene_hash = u64::from_le_bytes(read_array(&mut file)?);
+        let seed = u64::from_le_bytes(read_array(&mut file)?);
+        let max_depth = i32::from_le_bytes(read_array(&mut file)?);
+        let width = read_u32(&mut file)?;
+        let height = read_u32(&mut file)?;
+
+        let mut fb = Framebuffer::new(width, height);
+        for ((sum, sum_squares), count) in fb
+            .sums
+            .iter_mut()
+            .zip(&mut fb.sum_squares)
+            .zip(&mut fb.counts)
+        {
+            for i in 0..3 {
+                sum[i] = f64::from_le_bytes(read_array(&mut file)?);
+            }
+            *sum_squares = f64::from_le_bytes(read_array(&mut file)?);
+            *count = read_u32(&mut file)?;
+        }
+
+        Ok(Ok(Self {
+            scene,
+            scene_hash,
+            seed,
+            max_depth,
+            framebuffer: fb,
+        }))
+    }
+}
+
+fn read_array<const N: usize>(file: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut buf = [0; N];
+    file.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn read_u32(file: &mut impl Read) -> io::Result<u32> {
+    read_array(file).map(u32::from_le_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Vector3;
+
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut framebuffer = Framebuffer::new(3, 2);
+        for (i, ((sum, sum_squares), count)) in framebuffer
+            .sums
+            .iter_mut()
+            .zip(&mut framebuffer.sum_squares)
+            .zip(&mut framebuffer.counts)
+            .enumerate()
+        {
+            *sum = Vector3::new(i as f64, 0.1 / (i + 1) as f64, -1e300);
+            *sum_squares = (i as f64).sqrt();
+            *count = i as u32 * 7;
+        }
+        let checkpoint = Checkpoint {
+            scene: "scenes/ünïcode.toml".to_owned(),
+            scene_hash: 0x0123_4567_89ab_cdef,
+            seed: u64::MAX,
+            max_depth: 17,
+            framebuffer,
+        };
+
+        let path = std::env::temp_dir().join(format!("round-trip-{}.ckpt", std::process::id()));
+        checkpoint.save(&path).unwrap();
+        let loaded = Checkpoint::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+
+        assert_eq!(loaded.scene, checkpoint.scene);
+        assert_eq!(loaded.scene_hash, checkpoint.scene_hash);
+        assert_eq!(loaded.seed, checkpoint.seed);
+        assert_eq!(loaded.max_depth, checkpoint.max_depth);
+        assert_eq!(loaded.framebuffer.sums, checkpoint.framebuffer.sums);
+        assert_eq!(
+            loaded.framebuffer.sum_squares,
+            checkpoint.framebuffer.sum_squares
+        );
+        assert_eq!(loaded.framebuffer.counts, checkpoint.framebuffer.counts);
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        let path = std::env::temp_dir().join(format!("not-a-{}.ckpt", std::process::id()));
+        std::fs::write(&path, "P3\n1 1\n255\n0 0 0\n").unwrap();
+        let loaded = Checkpoint::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(loaded, Err(Error::NotACheckpoint(_))));
+    }
+
+    #[test]
+    fn rejects_other_precisions() {
+        let path = std::env::temp_dir().join(format!("precision-{}.ckpt", std::process::id()));
+        let other = if PRECISION == 64 { 32u32 } else { 64 };
+        std::fs::write(&path, [&MAGIC[..], &other.to_le_bytes()].concat()).unwrap();
+        let loaded = Checkpoint::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(loaded, Err(Error::OtherPrecision(_, bits)) if bits == other));
+    }
+}
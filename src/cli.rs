@@ -0,0 +1,106 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use raytracing_in_one_weekend::{
+    output::OutputFormat, render::Integrator, tiles::TileOrder, tonemap::Operator,
+};
+
+/// A raytracer, following the Ray Tracing in One Weekend series.
+///
+/// Settings given here override those in the scene file, which in turn override the defaults.
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Args {
+    /// One of the built-in scenes `random`, `cornell` or `cornell-smoke`, or the path to a TOML
+    /// scene file [default: random]
+    pub scene: Option<String>,
+
+    /// Image width in pixels, at least 2 [default: 1920]
+    #[arg(short = 'W', long, value_parser = clap::value_parser!(u32).range(2..))]
+    pub width: Option<u32>,
+
+    /// Image height in pixels, at least 2 [default: 1080]
+    #[arg(short = 'H', long, value_parser = clap::value_parser!(u32).range(2..))]
+    pub height: Option<u32>,
+
+    /// Samples per pixel [default: 100]
+    #[arg(short, long)]
+    pub samples: Option<usize>,
+
+    /// Maximum number of times a ray can bounce, at least 0 [default: 50]
+    #[arg(short = 'd', long, value_parser = clap::value_parser!(i32).range(0..))]
+    pub max_depth: Option<i32>,
+
+    /// File to write the image to
+    #[arg(short, long, default_value = "image.png")]
+    pub output: PathBuf,
+
+    /// Output format [default: guessed from the output file's extension]
+    #[arg(short, long, value_enum)]
+    pub format: Option<OutputFormat>,
+
+    /// How to fit bright parts of the image into the range of low dynamic range formats
+    #[arg(short, long, value_enum, default_value = "clamp")]
+    pub tone_map: Operator,
+
+    /// Stops to brighten low dynamic range output by, or darken if negative
+    #[arg(short, long, default_value_t = 0.0, allow_negative_numbers = true)]
+    pub exposure: f64,
+
+    /// Radiance that comes out white with the extended Reinhard and Hable operators
+    #[arg(long, default_value_t = 4.0)]
+    pub white_point: f64,
+
+    /// Number of render threads [default: one per CPU]
+    #[arg(short = 'j', long)]
+    pub threads: Option<usize>,
+
+    /// Width and height of the tiles the image is split into for rendering, in pixels
+    #[arg(long, default_value_t = 32)]
+    pub tile_size: usize,
+
+    /// Order to render tiles in
+    #[arg(long, value_enum, default_value = "spiral")]
+    pub tile_order: TileOrder,
+
+    /// How to trace paths. The image comes out the same either way
+    #[arg(long, value_enum, default_value = "recursive")]
+    pub integrator: Integrator,
+
+    /// CSV file to write how long each tile took to render in each pass to
+    #[arg(long)]
+    pub tile_times: Option<PathBuf>,
+
+    /// Seed for everything random about the scene and the render. Renders with the same seed
+    /// and settings are identical [default: random]
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Samples per pixel to add in each pass, writing out the image and checkpoint after every
+    /// pass [default: all of them in one pass]
+    #[arg(short, long)]
+    pub pass_samples: Option<usize>,
+
+    /// Once every pixel has `--samples` samples, keeps adding passes to pixels whose estimated
+    /// relative error is still above this, such as 0.01 for 1% [default: off]
+    #[arg(short, long)]
+    pub adaptive_threshold: Option<f64>,
+
+    /// Most samples a pixel can get with `--adaptive-threshold` [default: 8 times `--samples`]
+    #[arg(short, long)]
+    pub max_samples: Option<usize>,
+
+    /// File to write a heatmap of how many samples each pixel got to, brightest where most
+    #[arg(long)]
+    pub sample_map: Option<PathBuf>,
+
+    /// File to save the render so far to after every pass, to carry on from with `--resume`
+    #[arg(short, long)]
+    pub checkpoint: Option<PathBuf>,
+
+    /// Checkpoint to carry on a render from, adding passes until every pixel has `--samples`
+    /// samples. The scene, seed, size and maximum depth come from the checkpoint, which is
+    /// updated after each pass unless `--checkpoint` names another file
+    #[arg(short, long)]
+    pub resume: Option<PathBuf>,
+}
@@ -0,0 +1,91 @@
+use std::{fmt::Display, io, path::PathBuf};
+
+use crate::scene::file::SceneError;
+
+/// Anything that can go wrong loading scenes, or saving and loading renders.
+#[derive(Debug)]
+pub enum Error {
+    /// A scene that isn't built in and couldn't be loaded as a file.
+    Scene(String, SceneError),
+    Image(PathBuf, image::ImageError),
+    Io(PathBuf, io::Error),
+    /// A file that isn't a checkpoint, or is from another version of the program.
+    NotACheckpoint(PathBuf),
+    /// A checkpoint rendered with `Float`s of another number of bits.
+    OtherPrecision(PathBuf, u32),
+    /// A checkpoint being carried on with settings other than the ones it was started with.
+    CheckpointSettings {
+        path: PathBuf,
+        scene: String,
+        width: u32,
+        height: u32,
+        seed: u64,
+        max_depth: i32,
+    },
+    /// A checkpoint whose scene file has been edited since, with the checkpoint's path first.
+    SceneChanged(PathBuf, String),
+    /// An image path whose extension doesn't say what format to write it in.
+    UnknownFormat(PathBuf),
+    /// The render threads couldn't be started, with how many were asked for.
+    ThreadPool(usize, rayon::ThreadPoolBuildError),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Scene(name, e) => write!(f, "couldn't load {name}: {e}"),
+            Error::Image(path, e) => write!(f, "couldn't write {}: {e}", path.display()),
+            Error::Io(path, e) => write!(f, "couldn't access {}: {e}", path.display()),
+            Error::NotACheckpoint(path) => {
+                write!(f, "{} isn't a checkpoint from this version", path.display())
+            }
+            Error::OtherPrecision(path, bits) => write!(
+                f,
+                "{} was rendered in {bits}-bit precision, and can only be carried on in the same",
+                path.display()
+            ),
+            Error::CheckpointSettings {
+                path,
+                scene,
+                width,
+                height,
+                seed,
+                max_depth,
+            } => write!(
+                f,
+                "{} is of {scene} at {width}x{height} with seed {seed} and maximum depth \
+                 {max_depth}, and can only be carried on with the same settings",
+                path.display()
+            ),
+            Error::SceneChanged(path, scene) => write!(
+                f,
+                "{scene} has changed since {} was saved, so it can't be carried on",
+                path.display()
+            ),
+            Error::UnknownFormat(path) => {
+                write!(f, "can't tell what format to write {} in", path.display())
+            }
+            Error::ThreadPool(threads, e) => {
+                write!(f, "couldn't start {threads} render threads: {e}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Scene(_, e) => Some(e),
+            Error::Image(_, e) => Some(e),
+            Error::Io(_, e) => Some(e),
+            Error::ThreadPool(_, e) => Some(e),
+            Error::NotACheckpoint(_)
+            | Error::OtherPrecision(..)
+            | Error::CheckpointSettings { .. }
+            | Error::SceneChanged(..)
+            | Error::UnknownFormat(_) => None,
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
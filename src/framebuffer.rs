@@ -0,0 +1,69 @@
+use std::path::Path;
+
+use cgmath::Vector3;
+use ndarray::Array2;
+
+use crate::{
+    output::{self, OutputFormat},
+    tonemap::ToneMapping,
+    Error, Result,
+};
+
+/// The samples rendered so far for every pixel, indexed by `(x, y)` with y running up the image.
+/// The sums are kept in `f64` whatever precision the geometry is in, so that pixels with many
+/// samples don't lose the later ones to rounding.
+#[derive(Clone)]
+pub struct Framebuffer {
+    pub sums: Array2<Vector3<f64>>,
+    /// The sum of the squared luminance of the samples, for estimating their variance.
+    pub sum_squares: Array2<f64>,
+    pub counts: Array2<u32>,
+}
+
+impl Framebuffer {
+    /// A framebuffer with no samples in it yet.
+    pub fn new(width: u32, height: u32) -> Self {
+        let dim = (width as usize, height as usize);
+        Self {
+            sums: Array2::zeros(dim),
+            sum_squares: Array2::zeros(dim),
+            counts: Array2::zeros(dim),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.sums.dim().0 as u32
+    }
+
+    pub fn height(&self) -> u32 {
+        self.sums.dim().1 as u32
+    }
+
+    /// The mean of the samples in a pixel, or black if it has none yet.
+    #[inline]
+    pub fn mean(&self, x: usize, y: usize) -> Vector3<f64> {
+        self.sums[(x, y)] / f64::from(self.counts[(x, y)].max(1))
+    }
+
+    pub fn total_samples(&self) -> u64 {
+        self.counts.iter().map(|&c| u64::from(c)).sum()
+    }
+
+    /// Writes out the image. High dynamic range formats get the mean radiance, linear and
+    /// unclamped; everything else goes through `tone_mapping`.
+    pub fn save(
+        &self,
+        path: &Path,
+        format: OutputFormat,
+        tone_mapping: &ToneMapping,
+    ) -> Result<()> {
+        output::write_image(self, path, format, tone_mapping)
+            .map_err(|e| Error::Image(path.into(), e))
+    }
+
+    /// Writes a heatmap of how many samples each pixel has, running from black for pixels with
+    /// none through red and yellow to white for the pixels with the most.
+    pub fn save_sample_map(&self, path: &Path) -> Result<()> {
+        output::write_sample_map(&self.counts, path).map_err(|e| Error::Image(path.into(), e))
+    }
+}
@@ -0,0 +1,132 @@
+use std::fmt::Display;
+
+use crate::{math::Float, ray::Ray, Vec3};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    /// A box containing nothing. The union of this with any other box is that box.
+    pub const EMPTY: Aabb = Aabb {
+        min: Vec3::new(Float::INFINITY, Float::INFINITY, Float::INFINITY),
+        max: Vec3::new(
+            Float::NEG_INFINITY,
+            Float::NEG_INFINITY,
+            Float::NEG_INFINITY,
+        ),
+    };
+
+    #[inline]
+    pub fn new(a: Vec3, b: Vec3) -> Self {
+        Self {
+            min: Vec3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+            max: Vec3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+        }
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    /// Grows the box where necessary so that it is at least `delta` thick along each axis, which
+    /// stops flat objects from having degenerate bounds.
+    #[inline]
+    pub fn pad(&self, delta: Float) -> Self {
+        let mut min = self.min;
+        let mut max = self.max;
+        for axis in 0..3 {
+            if max[axis] - min[axis] < delta {
+                min[axis] -= delta / 2.0;
+                max[axis] += delta / 2.0;
+            }
+        }
+        Self { min, max }
+    }
+
+    #[inline]
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            min: Vec3::new(
+                self.min.x.min(other.min.x),
+                self.min.y.min(other.min.y),
+                self.min.z.min(other.min.z),
+            ),
+            max: Vec3::new(
+                self.max.x.max(other.max.x),
+                self.max.y.max(other.max.y),
+                self.max.z.max(other.max.z),
+            ),
+        }
+    }
+
+    #[inline]
+    pub fn union_point(&self, p: Vec3) -> Self {
+        self.union(&Self { min: p, max: p })
+    }
+
+    #[inline]
+    pub fn centroid(&self) -> Vec3 {
+        0.5 * (self.min + self.max)
+    }
+
+    #[inline]
+    pub fn diagonal(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    #[inline]
+    pub fn surface_area(&self) -> Float {
+        let d = self.diagonal();
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+
+    /// Index of the axis along which the box is longest.
+    #[inline]
+    pub fn longest_axis(&self) -> usize {
+        let d = self.diagonal();
+        if d.x > d.y && d.x > d.z {
+            0
+        } else if d.y > d.z {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// Slab test. `inv_dir` is the componentwise reciprocal of `ray.direction`, which callers
+    /// traversing many boxes with the same ray should compute once.
+    #[inline]
+    pub fn hit(&self, ray: &Ray, inv_dir: &Vec3, mut t_min: Float, mut t_max: Float) -> bool {
+        for axis in 0..3 {
+            let mut t0 = (self.min[axis] - ray.origin[axis]) * inv_dir[axis];
+            let mut t1 = (self.max[axis] - ray.origin[axis]) * inv_dir[axis];
+            if inv_dir[axis].is_sign_negative() {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            t_min = t0.max(t_min);
+            t_max = t1.min(t_max);
+            if t_max < t_min {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+impl Display for Aabb {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            write!(f, "(empty)")
+        } else {
+            write!(
+                f,
+                "({}, {}, {}) to ({}, {}, {})",
+                self.min.x, self.min.y, self.min.z, self.max.x, self.max.y, self.max.z
+            )
+        }
+    }
+}
@@ -0,0 +1,363 @@
+use rand::Rng;
+
+use crate::{math::Float, ray::Ray, Vec3};
+
+use super::{aabb::Aabb, list::HittableList, HitRecord, Hittable};
+
+/// Number of buckets candidate SAH splits are binned into along each axis.
+const SAH_BUCKETS: usize = 12;
+/// Cost of visiting an interior node, relative to a single primitive intersection test.
+const TRAVERSAL_COST: Float = 0.125;
+const MAX_LEAF_SIZE: usize = 4;
+/// Bounds the size of the traversal stack. Subtrees that would be deeper become leaves.
+const MAX_DEPTH: usize = 64;
+
+/// A bounding volume hierarchy built with the surface area heuristic, flattened into a single
+/// array of nodes in depth-first order.
+pub struct Bvh {
+    nodes: Vec<BvhNode>,
+    objects: Vec<Hittable>,
+}
+
+struct BvhNode {
+    bounds: Aabb,
+    // For leaves, the index of the first object. For interior nodes, the index of the second
+    // child; the first child always directly follows its parent.
+    offset: usize,
+    // Zero for interior nodes.
+    count: usize,
+    axis: usize,
+}
+
+struct PrimitiveInfo {
+    index: usize,
+    bounds: Aabb,
+    centroid: Vec3,
+}
+
+#[derive(Clone, Copy)]
+struct Bucket {
+    count: usize,
+    bounds: Aabb,
+}
+
+impl Bvh {
+    pub fn new(list: HittableList, time_start: Float, time_end: Float) -> Self {
+        let mut primitives: Vec<_> = list
+            .0
+            .iter()
+            .enumerate()
+            .map(|(index, obj)| {
+                let bounds = obj.bounding_box(time_start, time_end);
+                PrimitiveInfo {
+                    index,
+                    bounds,
+                    centroid: bounds.centroid(),
+                }
+            })
+            .collect();
+
+        let mut nodes = Vec::with_capacity(2 * primitives.len());
+        if !primitives.is_empty() {
+            Self::build(&mut nodes, &mut primitives, 0, 0);
+        }
+
+        // Reorder the objects so that each leaf refers to a contiguous range.
+        let mut objects: Vec<_> = list.0.into_iter().map(Some).collect();
+        let objects = primitives
+            .iter()
+            .map(|p| objects[p.index].take().unwrap())
+            .collect();
+
+        Self { nodes, objects }
+    }
+
+    fn build(
+        nodes: &mut Vec<BvhNode>,
+        primitives: &mut [PrimitiveInfo],
+        offset: usize,
+        depth: usize,
+    ) -> usize {
+        let bounds = primitives
+            .iter()
+            .fold(Aabb::EMPTY, |acc, p| acc.union(&p.bounds));
+
+        let node_index = nodes.len();
+        nodes.push(BvhNode {
+            bounds,
+            offset,
+            count: primitives.len(),
+            axis: 0,
+        });
+
+        if primitives.len() == 1 || depth + 1 >= MAX_DEPTH {
+            return node_index;
+        }
+
+        let centroid_bounds = primitives
+            .iter()
+            .fold(Aabb::EMPTY, |acc, p| acc.union_point(p.centroid));
+        let axis = centroid_bounds.longest_axis();
+        let axis_min = centroid_bounds.min[axis];
+        let axis_extent = centroid_bounds.max[axis] - axis_min;
+
+        // All the centroids coincide, so there is no useful way to split them.
+        if axis_extent <= 0.0 {
+            return node_index;
+        }
+
+        let bucket_of = |p: &PrimitiveInfo| {
+            let b = (SAH_BUCKETS as Float * (p.centroid[axis] - axis_min) / axis_extent) as usize;
+            b.min(SAH_BUCKETS - 1)
+        };
+
+        let mut buckets = [Bucket {
+            count: 0,
+            bounds: Aabb::EMPTY,
+        }; SAH_BUCKETS];
+        for p in primitives.iter() {
+            let b = &mut buckets[bucket_of(p)];
+            b.count += 1;
+            b.bounds = b.bounds.union(&p.bounds);
+        }
+
+        // Cost of splitting after each bucket, computed with a forward and a backward sweep.
+        let mut costs = [0.0; SAH_BUCKETS - 1];
+        let mut left = Bucket {
+            count: 0,
+            bounds: Aabb::EMPTY,
+        };
+        for (i, bucket) in buckets[..SAH_BUCKETS - 1].iter().enumerate() {
+            left.count += bucket.count;
+            left.bounds = left.bounds.union(&bucket.bounds);
+            costs[i] = left.count as Float * left.bounds.surface_area();
+        }
+        let mut right = Bucket {
+            count: 0,
+            bounds: Aabb::EMPTY,
+        };
+        for (i, bucket) in buckets[1..].iter().enumerate().rev() {
+            right.count += bucket.count;
+            right.bounds = right.bounds.union(&bucket.bounds);
+            costs[i] += right.count as Float * right.bounds.surface_area();
+        }
+
+        let (split_bucket, min_cost) = costs
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.is_finite())
+            .fold(
+                (0, Float::INFINITY),
+                |(bi, bc), (i, &c)| {
+                    if c < bc {
+                        (i, c)
+                    } else {
+                        (bi, bc)
+                    }
+                },
+            );
+        let split_cost = TRAVERSAL_COST + min_cost / bounds.surface_area();
+        let leaf_cost = primitives.len() as Float;
+
+        if primitives.len() <= MAX_LEAF_SIZE && leaf_cost <= split_cost {
+            return node_index;
+        }
+
+        let mid = partition(primitives, |p| bucket_of(p) <= split_bucket);
+        let (left_prims, right_prims) = primitives.split_at_mut(mid);
+
+        Self::build(nodes, left_prims, offset, depth + 1);
+        let second = Self::build(nodes, right_prims, offset + mid, depth + 1);
+
+        let node = &mut nodes[node_index];
+        node.offset = second;
+        node.count = 0;
+        node.axis = axis;
+        node_index
+    }
+
+    #[inline]
+    pub fn hit(
+        &self,
+        ray: &Ray,
+        t_min: Float,
+        t_max: Float,
+        rng: &mut impl Rng,
+    ) -> Option<HitRecord> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let inv_dir = Vec3::new(
+            ray.direction.x.recip(),
+            ray.direction.y.recip(),
+            ray.direction.z.recip(),
+        );
+        let dir_is_neg = [
+            inv_dir.x.is_sign_negative(),
+            inv_dir.y.is_sign_negative(),
+            inv_dir.z.is_sign_negative(),
+        ];
+
+        let mut rec = None;
+        let mut closest_so_far = t_max;
+        let mut stack = [0usize; MAX_DEPTH];
+        let mut stack_len = 0;
+        let mut current = 0;
+
+        loop {
+            let node = &self.nodes[current];
+            if node.bounds.hit(ray, &inv_dir, t_min, closest_so_far) {
+                if node.count > 0 {
+                    for obj in &self.objects[node.offset..node.offset + node.count] {
+                        if let Some(hit) = obj.hit(ray, t_min, closest_so_far, rng) {
+                            closest_so_far = hit.t;
+                            rec = Some(hit);
+                        }
+                    }
+                } else {
+                    // Visit the nearer child first so that more of the far child gets culled.
+                    let (near, far) = if dir_is_neg[node.axis] {
+                        (node.offset, current + 1)
+                    } else {
+                        (current + 1, node.offset)
+                    };
+                    stack[stack_len] = far;
+                    stack_len += 1;
+                    current = near;
+                    continue;
+                }
+            }
+
+            if stack_len == 0 {
+                break rec;
+            }
+            stack_len -= 1;
+            current = stack[stack_len];
+        }
+    }
+
+    /// Number of primitives in the hierarchy, counting each sphere in a batch separately.
+    pub fn object_count(&self) -> usize {
+        self.objects
+            .iter()
+            .map(|obj| match obj {
+                Hittable::Spheres(batch) => batch.len(),
+                _ => 1,
+            })
+            .sum()
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn leaf_count(&self) -> usize {
+        self.nodes.iter().filter(|n| n.count > 0).count()
+    }
+
+    /// Length of the longest path from the root to a leaf, counting both ends.
+    pub fn depth(&self) -> usize {
+        fn depth_from(nodes: &[BvhNode], index: usize) -> usize {
+            let node = &nodes[index];
+            if node.count > 0 {
+                1
+            } else {
+                1 + depth_from(nodes, index + 1).max(depth_from(nodes, node.offset))
+            }
+        }
+
+        if self.nodes.is_empty() {
+            0
+        } else {
+            depth_from(&self.nodes, 0)
+        }
+    }
+
+    #[inline]
+    pub fn bounding_box(&self) -> Aabb {
+        self.nodes.first().map_or(Aabb::EMPTY, |n| n.bounds)
+    }
+}
+
+/// Moves every element satisfying `pred` before every element that does not, returning the
+/// number of elements that do.
+fn partition<T>(slice: &mut [T], pred: impl Fn(&T) -> bool) -> usize {
+    let mut first = 0;
+    for i in 0..slice.len() {
+        if pred(&slice[i]) {
+            slice.swap(first, i);
+            first += 1;
+        }
+    }
+    first
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hit::{moving_sphere::MovingSphere, sphere::Sphere},
+        mat::{lambertian::Lambertian, Material, Materials},
+        rng,
+        tex::Texture,
+    };
+
+    /// Spheres of very different sizes, some moving, each with its own material.
+    fn objects(rng: &mut impl Rng) -> HittableList {
+        let mut materials = Materials::new();
+        let objects = (0..500)
+            .map(|i| {
+                let material = materials.add(Material::Lambertian(Lambertian {
+                    albedo: Texture::Solid(Vec3::zero()),
+                }));
+                let centre = Vec3::random_with_range(rng, -20.0, 20.0);
+                let radius = if i % 50 == 0 {
+                    rng.gen_range(2.0..8.0)
+                } else {
+                    rng.gen_range(0.1..1.0)
+                };
+                if i % 3 == 0 {
+                    Hittable::MovingSphere(MovingSphere {
+                        centre_start: centre,
+                        centre_end: centre + Vec3::random_with_range(rng, -2.0, 2.0),
+                        time_start: 0.0,
+                        time_end: 1.0,
+                        radius,
+                        material,
+                    })
+                } else {
+                    Hittable::Sphere(Sphere {
+                        centre,
+                        radius,
+                        material,
+                    })
+                }
+            })
+            .collect();
+        HittableList(objects)
+    }
+
+    #[test]
+    fn hits_like_a_list() {
+        let list = objects(&mut rng::scene_rng(1));
+        let bvh = Bvh::new(objects(&mut rng::scene_rng(1)), 0.0, 1.0);
+        assert!(bvh.depth() > 1);
+
+        let mut rng = rng::scene_rng(2);
+        for _ in 0..10_000 {
+            let ray = Ray {
+                origin: Vec3::random_with_range(&mut rng, -25.0, 25.0),
+                direction: Vec3::random_unit_vector(&mut rng),
+                time: rng.gen(),
+            };
+            let t_max = rng.gen_range(1.0..50.0);
+            let expected = list.hit(&ray, 0.001, t_max, &mut rng);
+            let actual = bvh.hit(&ray, 0.001, t_max, &mut rng);
+            assert_eq!(
+                actual.map(|hit| (hit.t, hit.material)),
+                expected.map(|hit| (hit.t, hit.material)),
+            );
+        }
+    }
+}
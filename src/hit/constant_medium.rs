@@ -0,0 +1,74 @@
+use std::sync::Arc;
+
+use rand::Rng;
+
+use crate::{mat::MaterialId, math::Float, ray::Ray, Vec3};
+
+use super::{aabb::Aabb, HitRecord, Hittable};
+
+/// A volume of smoke, fog or mist of the same density throughout, filling a closed boundary
+/// object. Rays passing through it scatter at random, with the chance of getting through falling
+/// off exponentially with the distance travelled inside.
+pub struct ConstantMedium {
+    boundary: Arc<Hittable>,
+    neg_inv_density: Float,
+    /// Decides which way rays go once they scatter. The boundary's own material is never used.
+    phase_function: MaterialId,
+}
+
+impl ConstantMedium {
+    /// `boundary` must be closed and convex, so that every ray enters and leaves it at most once.
+    pub fn new(boundary: Arc<Hittable>, density: Float, phase_function: MaterialId) -> Self {
+        Self {
+            boundary,
+            neg_inv_density: -density.recip(),
+            phase_function,
+        }
+    }
+
+    pub fn hit(
+        &self,
+        ray: &Ray,
+        t_min: Float,
+        t_max: Float,
+        rng: &mut impl Rng,
+    ) -> Option<HitRecord> {
+        // Find where the ray enters and leaves the boundary, even if that's behind it, so rays
+        // starting inside the volume still scatter
+        let entry = self
+            .boundary
+            .hit(ray, Float::NEG_INFINITY, Float::INFINITY, rng)?;
+        let exit = self
+            .boundary
+            .hit(ray, entry.t + 0.0001, Float::INFINITY, rng)?;
+
+        let t_enter = entry.t.max(t_min).max(0.0);
+        let t_exit = exit.t.min(t_max);
+        if t_enter >= t_exit {
+            return None;
+        }
+
+        let ray_length = ray.direction.length();
+        let distance_inside = (t_exit - t_enter) * ray_length;
+        let hit_distance = self.neg_inv_density * (1.0 - rng.gen::<Float>()).ln();
+        if hit_distance > distance_inside {
+            return None;
+        }
+
+        let t = t_enter + hit_distance / ray_length;
+        Some(HitRecord {
+            p: ray.at(t),
+            // Meaningless inside a volume, and ignored by the phase function
+            normal: Vec3::UNIT_UP,
+            material: self.phase_function,
+            t,
+            u: 0.0,
+            v: 0.0,
+            front_face: true,
+        })
+    }
+
+    pub fn bounding_box(&self, time_start: Float, time_end: Float) -> Aabb {
+        self.boundary.bounding_box(time_start, time_end)
+    }
+}
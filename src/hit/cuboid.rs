@@ -0,0 +1,63 @@
+use crate::{mat::MaterialId, math::Float, ray::Ray, Vec3};
+
+use super::{
+    aabb::Aabb,
+    rect::{Plane, Rect},
+    HitRecord,
+};
+
+/// An axis-aligned box, made of six rectangles.
+pub struct Cuboid {
+    min: Vec3,
+    max: Vec3,
+    sides: [Rect; 6],
+}
+
+impl Cuboid {
+    /// The box with opposite corners at `a` and `b`, which can be given either way round.
+    pub fn new(a: Vec3, b: Vec3, material: MaterialId) -> Self {
+        let Aabb { min, max } = Aabb::new(a, b);
+        let side = |plane, min, max, k| Rect {
+            plane,
+            min,
+            max,
+            k,
+            material,
+        };
+
+        Self {
+            min,
+            max,
+            sides: [
+                side(Plane::Xy, (min.x, min.y), (max.x, max.y), min.z),
+                side(Plane::Xy, (min.x, min.y), (max.x, max.y), max.z),
+                side(Plane::Xz, (min.x, min.z), (max.x, max.z), min.y),
+                side(Plane::Xz, (min.x, min.z), (max.x, max.z), max.y),
+                side(Plane::Yz, (min.y, min.z), (max.y, max.z), min.x),
+                side(Plane::Yz, (min.y, min.z), (max.y, max.z), max.x),
+            ],
+        }
+    }
+
+    #[inline]
+    pub fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        let mut rec = None;
+        let mut closest_so_far = t_max;
+        for side in &self.sides {
+            if let Some(hit) = side.hit(ray, t_min, closest_so_far) {
+                closest_so_far = hit.t;
+                rec = Some(hit);
+            }
+        }
+        rec
+    }
+
+    pub fn sides(&self) -> &[Rect; 6] {
+        &self.sides
+    }
+
+    #[inline]
+    pub fn bounding_box(&self) -> Aabb {
+        Aabb::new(self.min, self.max)
+    }
+}
@@ -0,0 +1,101 @@
+use cgmath::InnerSpace;
+use rand::Rng;
+
+use crate::{
+    mat::{Material, MaterialId, Materials},
+    math::Float,
+    pdf::Pdf,
+    Vec3,
+};
+
+use super::{quad::Quad, rect::Rect, sphere::Sphere, triangle::Triangle, Hittable};
+
+/// An emitter that can be sampled directly, by picking a direction towards it.
+#[derive(Clone)]
+pub enum Light {
+    Sphere(Sphere),
+    Rect(Rect),
+    Quad(Quad),
+    Triangle(Triangle),
+}
+
+impl Light {
+    #[inline]
+    pub fn pdf_value(&self, origin: Vec3, direction: Vec3) -> Float {
+        match self {
+            Light::Sphere(sphere) => sphere.pdf_value(origin, direction),
+            Light::Rect(rect) => rect.pdf_value(origin, direction),
+            Light::Quad(quad) => quad.pdf_value(origin, direction),
+            Light::Triangle(triangle) => triangle.pdf_value(origin, direction),
+        }
+    }
+
+    #[inline]
+    pub fn random_towards(&self, origin: Vec3, rng: &mut impl Rng) -> Vec3 {
+        match self {
+            Light::Sphere(sphere) => sphere.random_towards(origin, rng),
+            Light::Rect(rect) => rect.random_towards(origin, rng),
+            Light::Quad(quad) => quad.random_towards(origin, rng),
+            Light::Triangle(triangle) => triangle.random_towards(origin, rng),
+        }
+    }
+}
+
+/// The lights in a scene that can be sampled directly. Emitters of any other kind, or inside
+/// transforms or media, still light the scene, but only when a ray happens to hit them.
+pub struct Lights(Vec<Light>);
+
+impl Lights {
+    /// Picks out the spheres, rectangles, quads, boxes and triangles among `objects` whose
+    /// material in `materials` is emissive.
+    pub fn find(objects: &[Hittable], materials: &Materials) -> Self {
+        let emissive = |&id: &MaterialId| matches!(materials[id], Material::DiffuseLight(_));
+        let mut lights = Vec::new();
+        for obj in objects {
+            match obj {
+                Hittable::Sphere(sphere) if emissive(&sphere.material) => {
+                    lights.push(Light::Sphere(sphere.clone()))
+                }
+                Hittable::Rect(rect) if emissive(&rect.material) => {
+                    lights.push(Light::Rect(rect.clone()))
+                }
+                Hittable::Quad(quad) if emissive(&quad.material) => {
+                    lights.push(Light::Quad(quad.clone()))
+                }
+                Hittable::Triangle(triangle) if emissive(&triangle.material) => {
+                    lights.push(Light::Triangle(triangle.clone()))
+                }
+                Hittable::Cuboid(cuboid) => lights.extend(
+                    cuboid
+                        .sides()
+                        .iter()
+                        .filter(|side| emissive(&side.material))
+                        .map(|side| Light::Rect(side.clone())),
+                ),
+                _ => {}
+            }
+        }
+        Self(lights)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Directions from `origin` towards any of the lights, each as likely to be picked as the
+    /// others. There must be at least one light.
+    pub fn pdf(&self, origin: Vec3) -> Pdf<'_> {
+        match &self.0[..] {
+            [light] => Pdf::Light { light, origin },
+            lights => Pdf::Lights { lights, origin },
+        }
+    }
+}
+
+/// Density over solid angle of having picked a point uniformly on a flat light of the given
+/// `area`, when that point is `t` along `direction` and the light faces along `normal`.
+pub fn planar_pdf(direction: Vec3, t: Float, normal: Vec3, area: Float) -> Float {
+    let distance_squared = (t * direction).length_squared();
+    let cosine = (direction.dot(normal.0) / direction.length()).abs();
+    distance_squared / (cosine * area)
+}
@@ -1,20 +1,35 @@
-use crate::ray::Ray;
+use rand::Rng;
 
-use super::{HitRecord, Hittable};
+use crate::{math::Float, ray::Ray};
+
+use super::{aabb::Aabb, HitRecord, Hittable};
 
 pub struct HittableList(pub Vec<Hittable>);
 
 impl HittableList {
     #[inline]
-    pub fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    pub fn hit(
+        &self,
+        ray: &Ray,
+        t_min: Float,
+        t_max: Float,
+        rng: &mut impl Rng,
+    ) -> Option<HitRecord> {
         let mut rec = None;
         let mut closest_so_far = t_max;
         for obj in &self.0 {
-            if let Some(hit) = obj.hit(ray, t_min, closest_so_far) {
+            if let Some(hit) = obj.hit(ray, t_min, closest_so_far, rng) {
                 closest_so_far = hit.t;
                 rec = Some(hit);
             }
         }
         rec
     }
+
+    #[inline]
+    pub fn bounding_box(&self, time_start: Float, time_end: Float) -> Aabb {
+        self.0.iter().fold(Aabb::EMPTY, |acc, obj| {
+            acc.union(&obj.bounding_box(time_start, time_end))
+        })
+    }
 }
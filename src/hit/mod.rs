@@ -1,20 +1,33 @@
+pub mod aabb;
+pub mod bvh;
+pub mod constant_medium;
+pub mod cuboid;
+pub mod light;
 pub mod list;
 pub mod moving_sphere;
+pub mod quad;
+pub mod rect;
 pub mod sphere;
+pub mod sphere_batch;
+pub mod transform;
+pub mod triangle;
 
-use std::hint::unreachable_unchecked;
+use std::sync::Arc;
 
 use cgmath::InnerSpace;
+use rand::{Rng, RngCore};
 
-use crate::{mat::Material, ray::Ray, Vec3};
+use crate::{mat::MaterialId, math::Float, ray::Ray, Vec3};
 
-use self::list::HittableList;
+use self::{aabb::Aabb, bvh::Bvh, list::HittableList};
 
 pub struct HitRecord {
     pub p: Vec3,
     pub normal: Vec3,
-    pub material: Material,
-    pub t: f64,
+    pub material: MaterialId,
+    pub t: Float,
+    pub u: Float,
+    pub v: Float,
     pub front_face: bool,
 }
 
@@ -30,22 +43,80 @@ impl HitRecord {
     }
 }
 
+/// Geometry from outside this crate, put in a scene as a `Hittable::Custom`. The built-in shapes
+/// are matched on directly instead, which is faster.
+pub trait Shape: Send + Sync {
+    /// `rng` is for shapes that scatter rays at random, like participating media.
+    fn hit(
+        &self,
+        ray: &Ray,
+        t_min: Float,
+        t_max: Float,
+        rng: &mut dyn RngCore,
+    ) -> Option<HitRecord>;
+
+    /// Bounds of the shape over the whole of the interval `time_start..time_end`.
+    fn bounding_box(&self, time_start: Float, time_end: Float) -> Aabb;
+}
+
 pub enum Hittable {
     Sphere(sphere::Sphere),
     MovingSphere(moving_sphere::MovingSphere),
-    #[allow(dead_code)]
+    Spheres(Box<sphere_batch::SphereBatch>),
+    Triangle(triangle::Triangle),
+    Rect(rect::Rect),
+    Quad(quad::Quad),
+    Cuboid(Box<cuboid::Cuboid>),
+    Transform(Box<transform::Transform>),
+    ConstantMedium(constant_medium::ConstantMedium),
+    Bvh(Bvh),
     List(HittableList),
+    Custom(Arc<dyn Shape>),
 }
 
 impl Hittable {
+    /// `rng` is only used by participating media, to pick where inside them a ray scatters, and by
+    /// custom shapes.
     #[inline]
-    pub fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    pub fn hit(
+        &self,
+        ray: &Ray,
+        t_min: Float,
+        t_max: Float,
+        rng: &mut impl Rng,
+    ) -> Option<HitRecord> {
         match self {
             Hittable::Sphere(sphere) => sphere.hit(ray, t_min, t_max),
             Hittable::MovingSphere(sphere) => sphere.hit(ray, t_min, t_max),
-            // Safety:
-            // This variant is never constructed
-            Hittable::List(_) => unsafe { unreachable_unchecked() },
+            Hittable::Spheres(batch) => batch.hit(ray, t_min, t_max),
+            Hittable::Triangle(triangle) => triangle.hit(ray, t_min, t_max),
+            Hittable::Rect(rect) => rect.hit(ray, t_min, t_max),
+            Hittable::Quad(quad) => quad.hit(ray, t_min, t_max),
+            Hittable::Cuboid(cuboid) => cuboid.hit(ray, t_min, t_max),
+            Hittable::Transform(transform) => transform.hit(ray, t_min, t_max, rng),
+            Hittable::ConstantMedium(medium) => medium.hit(ray, t_min, t_max, rng),
+            Hittable::Bvh(bvh) => bvh.hit(ray, t_min, t_max, rng),
+            Hittable::List(list) => list.hit(ray, t_min, t_max, rng),
+            Hittable::Custom(shape) => shape.hit(ray, t_min, t_max, rng),
+        }
+    }
+
+    /// Bounds of the object over the whole of the interval `time_start..time_end`.
+    #[inline]
+    pub fn bounding_box(&self, time_start: Float, time_end: Float) -> Aabb {
+        match self {
+            Hittable::Sphere(sphere) => sphere.bounding_box(),
+            Hittable::MovingSphere(sphere) => sphere.bounding_box(time_start, time_end),
+            Hittable::Spheres(batch) => batch.bounding_box(time_start, time_end),
+            Hittable::Triangle(triangle) => triangle.bounding_box(),
+            Hittable::Rect(rect) => rect.bounding_box(),
+            Hittable::Quad(quad) => quad.bounding_box(),
+            Hittable::Cuboid(cuboid) => cuboid.bounding_box(),
+            Hittable::Transform(transform) => transform.bounding_box(time_start, time_end),
+            Hittable::ConstantMedium(medium) => medium.bounding_box(time_start, time_end),
+            Hittable::Bvh(bvh) => bvh.bounding_box(),
+            Hittable::List(list) => list.bounding_box(time_start, time_end),
+            Hittable::Custom(shape) => shape.bounding_box(time_start, time_end),
         }
     }
 }
@@ -1,34 +1,34 @@
 use cgmath::InnerSpace;
 
-use crate::{mat::Material, ray::Ray, Vec3};
+use crate::{mat::MaterialId, math::Float, ray::Ray, Vec3};
 
-use super::HitRecord;
+use super::{aabb::Aabb, sphere::sphere_record, HitRecord};
 
 pub struct MovingSphere {
     pub centre_start: Vec3,
     pub centre_end: Vec3,
-    pub time_start: f64,
-    pub time_end: f64,
-    pub radius: f64,
-    pub material: Material,
+    pub time_start: Float,
+    pub time_end: Float,
+    pub radius: Float,
+    pub material: MaterialId,
 }
 
 impl MovingSphere {
     #[inline]
-    pub fn centre(&self, time: f64) -> Vec3 {
+    pub fn centre(&self, time: Float) -> Vec3 {
         self.centre_start
             + ((time - self.time_start) / (self.time_end - self.time_start))
                 * (self.centre_end - self.centre_start)
     }
 
     #[inline]
-    pub fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    pub fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
         let oc = ray.origin - self.centre(ray.time);
         let a = ray.direction.length_squared();
         let half_b = oc.dot(ray.direction.0);
         let c = oc.length_squared() - self.radius.powi(2);
 
-        let discriminant: f64 = half_b.powi(2) - a * c;
+        let discriminant: Float = half_b.powi(2) - a * c;
         if discriminant.is_sign_negative() {
             return None;
         }
@@ -43,18 +43,20 @@ impl MovingSphere {
             }
         }
 
-        let p = ray.at(root);
-        let t = root;
-
-        let outward_normal = (p - self.centre(ray.time)) / self.radius;
-        let (front_face, normal) = HitRecord::face_and_normal(ray, outward_normal);
+        Some(sphere_record(
+            ray,
+            root,
+            self.centre(ray.time),
+            self.radius,
+            self.material,
+        ))
+    }
 
-        Some(HitRecord {
-            p,
-            normal,
-            t,
-            front_face,
-            material: self.material.clone(),
-        })
+    #[inline]
+    pub fn bounding_box(&self, time_start: Float, time_end: Float) -> Aabb {
+        let r = Vec3::new(self.radius, self.radius, self.radius);
+        let start = self.centre(time_start);
+        let end = self.centre(time_end);
+        Aabb::new(start - r, start + r).union(&Aabb::new(end - r, end + r))
     }
 }
@@ -0,0 +1,100 @@
+use cgmath::InnerSpace;
+use rand::Rng;
+
+use crate::{mat::MaterialId, math::Float, ray::Ray, Vec3};
+
+use super::{aabb::Aabb, light, HitRecord};
+
+/// A parallelogram with one corner at `corner` and sides `u` and `v`.
+#[derive(Clone)]
+pub struct Quad {
+    corner: Vec3,
+    u: Vec3,
+    v: Vec3,
+    normal: Vec3,
+    // The plane is the set of points p with normal · p = d
+    d: Float,
+    // Takes a point in the plane relative to `corner` to its coordinates along `u` and `v`
+    w: Vec3,
+    pub material: MaterialId,
+}
+
+impl Quad {
+    pub fn new(corner: Vec3, u: Vec3, v: Vec3, material: MaterialId) -> Self {
+        let n: Vec3 = u.cross(v.0).into();
+        let normal = n.unit_vec();
+        Self {
+            corner,
+            u,
+            v,
+            normal,
+            d: normal.dot(corner.0),
+            w: n / n.length_squared(),
+            material,
+        }
+    }
+
+    #[inline]
+    pub fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        let denom = self.normal.dot(ray.direction.0);
+        // Parallel to the plane
+        if denom.abs() < 1e-8 {
+            return None;
+        }
+
+        let t = (self.d - self.normal.dot(ray.origin.0)) / denom;
+        if !(t_min..=t_max).contains(&t) {
+            return None;
+        }
+
+        let p = ray.at(t);
+        let planar = p - self.corner;
+        let alpha = self.w.dot(planar.cross(self.v.0));
+        let beta = self.w.dot(self.u.cross(planar.0));
+        if !(0.0..=1.0).contains(&alpha) || !(0.0..=1.0).contains(&beta) {
+            return None;
+        }
+
+        let (front_face, normal) = HitRecord::face_and_normal(ray, self.normal);
+
+        Some(HitRecord {
+            p,
+            normal,
+            t,
+            u: alpha,
+            v: beta,
+            front_face,
+            material: self.material,
+        })
+    }
+
+    /// Probability density, over solid angle, of `random_towards` picking `direction` from
+    /// `origin`.
+    pub fn pdf_value(&self, origin: Vec3, direction: Vec3) -> Float {
+        let ray = Ray {
+            origin,
+            direction,
+            time: 0.0,
+        };
+        match self.hit(&ray, 0.001, Float::INFINITY) {
+            Some(rec) => {
+                let area = self.u.cross(self.v.0).magnitude();
+                light::planar_pdf(direction, rec.t, self.normal, area)
+            }
+            None => 0.0,
+        }
+    }
+
+    /// A direction from `origin` towards a point chosen uniformly on the quad.
+    pub fn random_towards(&self, origin: Vec3, rng: &mut impl Rng) -> Vec3 {
+        let (alpha, beta): (Float, Float) = rng.gen();
+        self.corner + alpha * self.u + beta * self.v - origin
+    }
+
+    #[inline]
+    pub fn bounding_box(&self) -> Aabb {
+        Aabb::new(self.corner, self.corner + self.u + self.v)
+            .union(&Aabb::new(self.corner + self.u, self.corner + self.v))
+            .pad(1e-4)
+    }
+}
@@ -0,0 +1,129 @@
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{mat::MaterialId, math::Float, ray::Ray, Vec3};
+
+use super::{aabb::Aabb, light, HitRecord};
+
+/// The plane an axis-aligned rectangle lies in.
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Plane {
+    Xy,
+    Xz,
+    Yz,
+}
+
+impl Plane {
+    /// The two axes spanning the plane, followed by the axis normal to it.
+    #[inline]
+    fn axes(self) -> (usize, usize, usize) {
+        match self {
+            Plane::Xy => (0, 1, 2),
+            Plane::Xz => (0, 2, 1),
+            Plane::Yz => (1, 2, 0),
+        }
+    }
+}
+
+/// A rectangle in `plane` at `k` along the third axis, covering `min..max` along the two axes in
+/// the plane's name.
+#[derive(Clone)]
+pub struct Rect {
+    pub plane: Plane,
+    pub min: (Float, Float),
+    pub max: (Float, Float),
+    pub k: Float,
+    pub material: MaterialId,
+}
+
+impl Rect {
+    /// The rectangle in `plane` at `k` with opposite corners at `a` and `b`, which can be given
+    /// either way round.
+    pub fn new(
+        plane: Plane,
+        a: (Float, Float),
+        b: (Float, Float),
+        k: Float,
+        material: MaterialId,
+    ) -> Self {
+        Self {
+            plane,
+            min: (a.0.min(b.0), a.1.min(b.1)),
+            max: (a.0.max(b.0), a.1.max(b.1)),
+            k,
+            material,
+        }
+    }
+
+    #[inline]
+    pub fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        let (a, b, n) = self.plane.axes();
+
+        let t = (self.k - ray.origin[n]) / ray.direction[n];
+        if !(t_min..=t_max).contains(&t) {
+            return None;
+        }
+
+        let p = ray.at(t);
+        let (pa, pb) = (p[a], p[b]);
+        if pa < self.min.0 || pa > self.max.0 || pb < self.min.1 || pb > self.max.1 {
+            return None;
+        }
+
+        let mut outward_normal = Vec3::zero();
+        outward_normal[n] = 1.0;
+        let (front_face, normal) = HitRecord::face_and_normal(ray, outward_normal);
+
+        Some(HitRecord {
+            p,
+            normal,
+            t,
+            u: (pa - self.min.0) / (self.max.0 - self.min.0),
+            v: (pb - self.min.1) / (self.max.1 - self.min.1),
+            front_face,
+            material: self.material,
+        })
+    }
+
+    /// Probability density, over solid angle, of `random_towards` picking `direction` from
+    /// `origin`.
+    pub fn pdf_value(&self, origin: Vec3, direction: Vec3) -> Float {
+        let ray = Ray {
+            origin,
+            direction,
+            time: 0.0,
+        };
+        match self.hit(&ray, 0.001, Float::INFINITY) {
+            Some(rec) => {
+                let area = (self.max.0 - self.min.0) * (self.max.1 - self.min.1);
+                light::planar_pdf(direction, rec.t, rec.normal, area)
+            }
+            None => 0.0,
+        }
+    }
+
+    /// A direction from `origin` towards a point chosen uniformly on the rectangle.
+    pub fn random_towards(&self, origin: Vec3, rng: &mut impl Rng) -> Vec3 {
+        let (a, b, n) = self.plane.axes();
+        let mut p = Vec3::zero();
+        p[a] = rng.gen_range(self.min.0..=self.max.0);
+        p[b] = rng.gen_range(self.min.1..=self.max.1);
+        p[n] = self.k;
+        p - origin
+    }
+
+    #[inline]
+    pub fn bounding_box(&self) -> Aabb {
+        let (a, b, n) = self.plane.axes();
+        let mut min = Vec3::zero();
+        let mut max = Vec3::zero();
+        min[a] = self.min.0;
+        min[b] = self.min.1;
+        min[n] = self.k;
+        max[a] = self.max.0;
+        max[b] = self.max.1;
+        max[n] = self.k;
+        Aabb::new(min, max).pad(1e-4)
+    }
+}
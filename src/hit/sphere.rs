@@ -1,24 +1,65 @@
 use cgmath::InnerSpace;
+use rand::Rng;
 
-use crate::{mat::Material, ray::Ray, Vec3};
+use crate::{
+    mat::MaterialId,
+    math::{consts::PI, Float, Onb},
+    ray::Ray,
+    Vec3,
+};
 
-use super::HitRecord;
+use super::{aabb::Aabb, HitRecord};
 
+/// Surface coordinates of a point `p` on the unit sphere. `u` is the angle around the y axis,
+/// starting from -x, and `v` is the angle from the -y pole, both scaled to `0..=1`.
+#[inline]
+pub fn sphere_uv(p: Vec3) -> (Float, Float) {
+    let theta = (-p.y).acos();
+    let phi = (-p.z).atan2(p.x) + PI;
+    (phi / (2.0 * PI), theta / PI)
+}
+
+/// The record of `ray` hitting a sphere, wherever it is at the time of the ray, at `t`.
+#[inline]
+pub(super) fn sphere_record(
+    ray: &Ray,
+    t: Float,
+    centre: Vec3,
+    radius: Float,
+    material: MaterialId,
+) -> HitRecord {
+    let p = ray.at(t);
+    let outward_normal = (p - centre) / radius;
+    let (front_face, normal) = HitRecord::face_and_normal(ray, outward_normal);
+    let (u, v) = sphere_uv(outward_normal);
+
+    HitRecord {
+        p,
+        normal,
+        t,
+        u,
+        v,
+        front_face,
+        material,
+    }
+}
+
+#[derive(Clone)]
 pub struct Sphere {
     pub centre: Vec3,
-    pub radius: f64,
-    pub material: Material,
+    pub radius: Float,
+    pub material: MaterialId,
 }
 
 impl Sphere {
     #[inline]
-    pub fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    pub fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
         let oc = ray.origin - self.centre;
         let a = ray.direction.length_squared();
         let half_b = oc.dot(ray.direction.0);
         let c = oc.length_squared() - self.radius.powi(2);
 
-        let discriminant: f64 = half_b.powi(2) - a * c;
+        let discriminant: Float = half_b.powi(2) - a * c;
         if discriminant.is_sign_negative() {
             return None;
         }
@@ -33,18 +74,49 @@ impl Sphere {
             }
         }
 
-        let p = ray.at(root);
-        let t = root;
+        Some(sphere_record(
+            ray,
+            root,
+            self.centre,
+            self.radius,
+            self.material,
+        ))
+    }
+
+    /// Probability density, over solid angle, of `random_towards` picking `direction` from
+    /// `origin`.
+    pub fn pdf_value(&self, origin: Vec3, direction: Vec3) -> Float {
+        let ray = Ray {
+            origin,
+            direction,
+            time: 0.0,
+        };
+        if self.hit(&ray, 0.001, Float::INFINITY).is_none() {
+            return 0.0;
+        }
+
+        let cos_theta_max =
+            (1.0 - self.radius.powi(2) / (self.centre - origin).length_squared()).sqrt();
+        let solid_angle = 2.0 * PI * (1.0 - cos_theta_max);
+        solid_angle.recip()
+    }
 
-        let outward_normal = (p - self.centre) / self.radius;
-        let (front_face, normal) = HitRecord::face_and_normal(ray, outward_normal);
+    /// A direction from `origin` chosen uniformly from the cone the sphere fills, as seen from
+    /// there.
+    pub fn random_towards(&self, origin: Vec3, rng: &mut impl Rng) -> Vec3 {
+        let to_centre = self.centre - origin;
+        let cos_theta_max = (1.0 - self.radius.powi(2) / to_centre.length_squared()).sqrt();
 
-        Some(HitRecord {
-            p,
-            normal,
-            t,
-            front_face,
-            material: self.material.clone(),
-        })
+        let (r1, r2): (Float, Float) = rng.gen();
+        let z = 1.0 + r2 * (cos_theta_max - 1.0);
+        let phi = 2.0 * PI * r1;
+        let sin_theta = (1.0 - z.powi(2)).sqrt();
+        Onb::from_w(to_centre).local(Vec3::new(phi.cos() * sin_theta, phi.sin() * sin_theta, z))
+    }
+
+    #[inline]
+    pub fn bounding_box(&self) -> Aabb {
+        let r = Vec3::new(self.radius, self.radius, self.radius);
+        Aabb::new(self.centre - r, self.centre + r)
     }
 }
@@ -0,0 +1,370 @@
+use crate::{mat::MaterialId, math::Float, ray::Ray, Vec3};
+
+use super::{
+    aabb::Aabb, list::HittableList, moving_sphere::MovingSphere, sphere::sphere_record,
+    sphere::Sphere, HitRecord, Hittable,
+};
+
+/// Number of spheres tested together, enough to fill a 256-bit vector register.
+pub const LANES: usize = 32 / std::mem::size_of::<Float>();
+
+/// Up to `LANES` spheres, static or moving, stored a field at a time so that a ray can be tested
+/// against all of them at once. Each field is an array with one lane per sphere, and the
+/// intersection loop over the lanes has no branches, so it compiles to vector instructions.
+///
+/// Static spheres are stored as moving spheres that don't move. Lanes past `len` are padding,
+/// with a centre of NaN that no ray can hit.
+pub struct SphereBatch {
+    centre_x: [Float; LANES],
+    centre_y: [Float; LANES],
+    centre_z: [Float; LANES],
+    // How far the centre moves from `time_start` to `time_start + duration`.
+    delta_x: [Float; LANES],
+    delta_y: [Float; LANES],
+    delta_z: [Float; LANES],
+    time_start: [Float; LANES],
+    duration: [Float; LANES],
+    radius: [Float; LANES],
+    material: [MaterialId; LANES],
+    len: usize,
+}
+
+/// One sphere on its way into a batch.
+struct Lane {
+    /// Where the sphere came from in the list being packed.
+    index: usize,
+    centre: Vec3,
+    delta: Vec3,
+    time_start: Float,
+    duration: Float,
+    radius: Float,
+    material: MaterialId,
+}
+
+impl Lane {
+    /// Where the sphere is half way through its movement, which is what batches are sorted by.
+    fn midpoint(&self) -> Vec3 {
+        self.centre + 0.5 * self.delta
+    }
+
+    /// Bounds of the sphere over the whole of its movement.
+    fn bounds(&self) -> Aabb {
+        let r = Vec3::new(self.radius, self.radius, self.radius);
+        let end = self.centre + self.delta;
+        Aabb::new(self.centre - r, self.centre + r).union(&Aabb::new(end - r, end + r))
+    }
+}
+
+impl SphereBatch {
+    fn new(lanes: &[Lane]) -> Self {
+        assert!(!lanes.is_empty() && lanes.len() <= LANES);
+        let mut batch = Self {
+            centre_x: [Float::NAN; LANES],
+            centre_y: [Float::NAN; LANES],
+            centre_z: [Float::NAN; LANES],
+            delta_x: [0.0; LANES],
+            delta_y: [0.0; LANES],
+            delta_z: [0.0; LANES],
+            time_start: [0.0; LANES],
+            duration: [1.0; LANES],
+            radius: [0.0; LANES],
+            material: [lanes[0].material; LANES],
+            len: lanes.len(),
+        };
+        for (i, lane) in lanes.iter().enumerate() {
+            batch.centre_x[i] = lane.centre.x;
+            batch.centre_y[i] = lane.centre.y;
+            batch.centre_z[i] = lane.centre.z;
+            batch.delta_x[i] = lane.delta.x;
+            batch.delta_y[i] = lane.delta.y;
+            batch.delta_z[i] = lane.delta.z;
+            batch.time_start[i] = lane.time_start;
+            batch.duration[i] = lane.duration;
+            batch.radius[i] = lane.radius;
+            batch.material[i] = lane.material;
+        }
+        batch
+    }
+
+    /// Centre of the sphere in lane `i` at `time`, the same as `MovingSphere::centre`.
+    #[inline]
+    fn centre(&self, i: usize, time: Float) -> Vec3 {
+        let s = (time - self.time_start[i]) / self.duration[i];
+        Vec3::new(
+            self.centre_x[i] + s * self.delta_x[i],
+            self.centre_y[i] + s * self.delta_y[i],
+            self.centre_z[i] + s * self.delta_z[i],
+        )
+    }
+
+    /// The nearest hit among all the spheres in the batch. Each lane does the same arithmetic as
+    /// `MovingSphere::hit`, so the result is the same as testing the spheres one at a time.
+    #[inline]
+    pub fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        let (ox, oy, oz) = (ray.origin.x, ray.origin.y, ray.origin.z);
+        let (dx, dy, dz) = (ray.direction.x, ray.direction.y, ray.direction.z);
+        let a = ray.direction.length_squared();
+
+        let mut half_bs = [0.0; LANES];
+        let mut discriminants = [0.0; LANES];
+        for i in 0..LANES {
+            let s = (ray.time - self.time_start[i]) / self.duration[i];
+            let ocx = ox - (self.centre_x[i] + s * self.delta_x[i]);
+            let ocy = oy - (self.centre_y[i] + s * self.delta_y[i]);
+            let ocz = oz - (self.centre_z[i] + s * self.delta_z[i]);
+
+            let half_b = ocx * dx + ocy * dy + ocz * dz;
+            let c = (ocx * ocx + ocy * ocy + ocz * ocz) - self.radius[i] * self.radius[i];
+            half_bs[i] = half_b;
+            discriminants[i] = half_b * half_b - a * c;
+        }
+
+        // Most rays miss every sphere, and can skip the square roots and divisions. Padding
+        // lanes have NaN discriminants, so they never count as possible hits.
+        let any_possible = discriminants
+            .iter()
+            .fold(false, |any, &discriminant| any | (discriminant >= 0.0));
+        if !any_possible {
+            return None;
+        }
+
+        // Misses are left at infinity.
+        let mut roots = [Float::INFINITY; LANES];
+        for i in 0..LANES {
+            let (half_b, discriminant) = (half_bs[i], discriminants[i]);
+            // NaN where there is no intersection, which fails every comparison below
+            let sqrtd = discriminant.sqrt();
+
+            let near = (-half_b - sqrtd) / a;
+            let far = (-half_b + sqrtd) / a;
+            let root = if t_min <= near && near <= t_max {
+                near
+            } else {
+                far
+            };
+            let hit = !discriminant.is_sign_negative() && t_min <= root && root <= t_max;
+            roots[i] = if hit { root } else { Float::INFINITY };
+        }
+
+        let mut nearest = None;
+        let mut closest_so_far = Float::INFINITY;
+        for (i, &root) in roots.iter().enumerate() {
+            if root < closest_so_far {
+                closest_so_far = root;
+                nearest = Some(i);
+            }
+        }
+
+        nearest.map(|i| {
+            sphere_record(
+                ray,
+                roots[i],
+                self.centre(i, ray.time),
+                self.radius[i],
+                self.material[i],
+            )
+        })
+    }
+
+    /// Number of spheres in the batch, not counting padding.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Always false, as batches have at least one sphere.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    pub fn bounding_box(&self, time_start: Float, time_end: Float) -> Aabb {
+        (0..self.len).fold(Aabb::EMPTY, |acc, i| {
+            let r = Vec3::new(self.radius[i], self.radius[i], self.radius[i]);
+            let start = self.centre(i, time_start);
+            let end = self.centre(i, time_end);
+            acc.union(&Aabb::new(start - r, start + r))
+                .union(&Aabb::new(end - r, end + r))
+        })
+    }
+}
+
+/// Relative cost of testing a ray against a sphere on its own rather than against a batch.
+const SCALAR_COST: Float = 0.5;
+
+/// Gathers the spheres and moving spheres among `objects` into batches of nearby spheres, so that
+/// the BVH built over them has fewer, fuller leaves. Everything else is left as it is.
+pub fn pack(objects: HittableList) -> HittableList {
+    let mut lanes = Vec::new();
+    let mut spheres = Vec::new();
+    let mut packed = Vec::with_capacity(objects.0.len());
+    for obj in objects.0 {
+        let index = spheres.len();
+        let lane = match obj {
+            Hittable::Sphere(Sphere {
+                centre,
+                radius,
+                material,
+            }) => Lane {
+                index,
+                centre,
+                delta: Vec3::new(0.0, 0.0, 0.0),
+                time_start: 0.0,
+                duration: 1.0,
+                radius,
+                material,
+            },
+            Hittable::MovingSphere(MovingSphere {
+                centre_start,
+                centre_end,
+                time_start,
+                time_end,
+                radius,
+                material,
+            }) => Lane {
+                index,
+                centre: centre_start,
+                delta: centre_end - centre_start,
+                time_start,
+                duration: time_end - time_start,
+                radius,
+                material,
+            },
+            _ => {
+                packed.push(obj);
+                continue;
+            }
+        };
+        lanes.push(lane);
+        spheres.push(Some(obj));
+    }
+
+    if !lanes.is_empty() {
+        split(&mut lanes, &mut spheres, &mut packed);
+    }
+    HittableList(packed)
+}
+
+/// Splits `lanes` in two until each part fits in a batch, or is a single sphere left as it was in
+/// `spheres`. Each split is the one, along any axis, that minimises the surface area of each part
+/// times the cost of testing it. Parts are either whole numbers of batches or single spheres, so
+/// that a few huge spheres end up on their own rather than stretching the batches around them.
+fn split(lanes: &mut [Lane], spheres: &mut [Option<Hittable>], packed: &mut Vec<Hittable>) {
+    if let [lane] = lanes {
+        packed.push(spheres[lane.index].take().unwrap());
+        return;
+    }
+    if lanes.len() <= LANES {
+        packed.push(Hittable::Spheres(Box::new(SphereBatch::new(lanes))));
+        return;
+    }
+
+    let sort = |lanes: &mut [Lane], axis: usize| {
+        lanes.sort_by(|a, b| a.midpoint()[axis].total_cmp(&b.midpoint()[axis]))
+    };
+    let cost = |bounds: &Aabb, count: usize| {
+        let tests = if count == 1 {
+            SCALAR_COST
+        } else {
+            count.div_ceil(LANES) as Float
+        };
+        bounds.surface_area() * tests
+    };
+
+    // (cost, axis, mid), falling back to splitting off one batch if every cost is infinite
+    let mut best = (Float::INFINITY, 0, LANES);
+    for axis in 0..3 {
+        sort(lanes, axis);
+
+        // Bounds of everything from each lane onwards, swept from the back.
+        let mut right_bounds = vec![Aabb::EMPTY; lanes.len() + 1];
+        for (i, lane) in lanes.iter().enumerate().rev() {
+            right_bounds[i] = right_bounds[i + 1].union(&lane.bounds());
+        }
+
+        let mut left_bounds = Aabb::EMPTY;
+        for (i, lane) in lanes.iter().enumerate() {
+            left_bounds = left_bounds.union(&lane.bounds());
+            let mid = i + 1;
+            if mid < lanes.len() && (mid % LANES == 0 || mid == 1 || mid == lanes.len() - 1) {
+                let c = cost(&left_bounds, mid) + cost(&right_bounds[mid], lanes.len() - mid);
+                if c < best.0 {
+                    best = (c, axis, mid);
+                }
+            }
+        }
+    }
+
+    let (_, axis, mid) = best;
+    sort(lanes, axis);
+    let (left, right) = lanes.split_at_mut(mid);
+    split(left, spheres, packed);
+    split(right, spheres, packed);
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+    use crate::{
+        mat::{lambertian::Lambertian, Material, Materials},
+        rng,
+        tex::Texture,
+    };
+
+    /// Small spheres scattered through a box, half of them moving, each with its own material.
+    fn spheres(rng: &mut impl Rng) -> HittableList {
+        let mut materials = Materials::new();
+        let objects = (0..200)
+            .map(|i| {
+                let material = materials.add(Material::Lambertian(Lambertian {
+                    albedo: Texture::Solid(Vec3::zero()),
+                }));
+                let centre = Vec3::random_with_range(rng, -10.0, 10.0);
+                let radius = rng.gen_range(0.2..1.0);
+                if i % 2 == 0 {
+                    Hittable::Sphere(Sphere {
+                        centre,
+                        radius,
+                        material,
+                    })
+                } else {
+                    Hittable::MovingSphere(MovingSphere {
+                        centre_start: centre,
+                        centre_end: centre + Vec3::random_with_range(rng, -1.0, 1.0),
+                        time_start: 0.0,
+                        time_end: 1.0,
+                        radius,
+                        material,
+                    })
+                }
+            })
+            .collect();
+        HittableList(objects)
+    }
+
+    #[test]
+    fn batches_hit_like_single_spheres() {
+        let scalar = spheres(&mut rng::scene_rng(1));
+        let packed = pack(spheres(&mut rng::scene_rng(1)));
+        assert!(packed
+            .0
+            .iter()
+            .any(|obj| matches!(obj, Hittable::Spheres(batch) if batch.len() > 1)));
+
+        let mut rng = rng::scene_rng(2);
+        for _ in 0..10_000 {
+            let ray = Ray {
+                origin: Vec3::random_with_range(&mut rng, -12.0, 12.0),
+                direction: Vec3::random_unit_vector(&mut rng),
+                time: rng.gen(),
+            };
+            let expected = scalar.hit(&ray, 0.001, Float::INFINITY, &mut rng);
+            let actual = packed.hit(&ray, 0.001, Float::INFINITY, &mut rng);
+            assert_eq!(
+                actual.map(|hit| (hit.t, hit.p, hit.normal, hit.material, hit.u, hit.v)),
+                expected.map(|hit| (hit.t, hit.p, hit.normal, hit.material, hit.u, hit.v)),
+            );
+        }
+    }
+}
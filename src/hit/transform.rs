@@ -0,0 +1,173 @@
+use std::sync::Arc;
+
+use cgmath::{Deg, InnerSpace, Matrix, Matrix4, Quaternion, SquareMatrix};
+use rand::Rng;
+
+use crate::{math::Float, ray::Ray, Vec3};
+
+use super::{aabb::Aabb, HitRecord, Hittable};
+
+/// Places an object in the world with an arbitrary affine transform. The object is shared, so the
+/// same one can be placed in many spots.
+pub struct Transform {
+    object: Arc<Hittable>,
+    to_world: Matrix4<Float>,
+    to_object: Matrix4<Float>,
+}
+
+impl Transform {
+    /// Leaves the object where it is. Use the other methods to move it.
+    pub fn new(object: Arc<Hittable>) -> Self {
+        Self {
+            object,
+            to_world: Matrix4::identity(),
+            to_object: Matrix4::identity(),
+        }
+    }
+
+    /// `None` if `to_world` isn't invertible.
+    pub fn from_matrix(object: Arc<Hittable>, to_world: Matrix4<Float>) -> Option<Self> {
+        Some(Self {
+            object,
+            to_world,
+            to_object: to_world.invert()?,
+        })
+    }
+
+    /// Applies `m`, whose inverse is `m_inv`, after the existing transform.
+    fn then(self, m: Matrix4<Float>, m_inv: Matrix4<Float>) -> Self {
+        Self {
+            object: self.object,
+            to_world: m * self.to_world,
+            to_object: self.to_object * m_inv,
+        }
+    }
+
+    pub fn translated(self, offset: Vec3) -> Self {
+        self.then(
+            Matrix4::from_translation(offset.0),
+            Matrix4::from_translation(-offset.0),
+        )
+    }
+
+    /// Rotates about the x axis, then the y axis, then the z axis.
+    pub fn rotated_euler(self, x: Deg<Float>, y: Deg<Float>, z: Deg<Float>) -> Self {
+        let m = Matrix4::from_angle_z(z) * Matrix4::from_angle_y(y) * Matrix4::from_angle_x(x);
+        // Rotations are orthogonal
+        self.then(m, m.transpose())
+    }
+
+    pub fn rotated(self, rotation: Quaternion<Float>) -> Self {
+        let rotation = rotation.normalize();
+        self.then(Matrix4::from(rotation), Matrix4::from(rotation.conjugate()))
+    }
+
+    /// `None` if any of the factors are zero.
+    pub fn scaled(self, factors: Vec3) -> Option<Self> {
+        if factors.x == 0.0 || factors.y == 0.0 || factors.z == 0.0 {
+            return None;
+        }
+        Some(self.then(
+            Matrix4::from_nonuniform_scale(factors.x, factors.y, factors.z),
+            Matrix4::from_nonuniform_scale(factors.x.recip(), factors.y.recip(), factors.z.recip()),
+        ))
+    }
+
+    #[inline]
+    fn point_to(m: &Matrix4<Float>, p: Vec3) -> Vec3 {
+        (m * p.0.extend(1.0)).truncate().into()
+    }
+
+    #[inline]
+    fn vector_to(m: &Matrix4<Float>, v: Vec3) -> Vec3 {
+        (m * v.0.extend(0.0)).truncate().into()
+    }
+
+    #[inline]
+    pub fn hit(
+        &self,
+        ray: &Ray,
+        t_min: Float,
+        t_max: Float,
+        rng: &mut impl Rng,
+    ) -> Option<HitRecord> {
+        // The direction isn't renormalised, so distances along the ray are the same in both spaces
+        let object_ray = Ray {
+            origin: Self::point_to(&self.to_object, ray.origin),
+            direction: Self::vector_to(&self.to_object, ray.direction),
+            time: ray.time,
+        };
+
+        let mut rec = self.object.hit(&object_ray, t_min, t_max, rng)?;
+
+        rec.p = Self::point_to(&self.to_world, rec.p);
+        // Normals transform by the inverse transpose
+        rec.normal = Self::vector_to(&self.to_object.transpose(), rec.normal).unit_vec();
+
+        Some(rec)
+    }
+
+    pub fn bounding_box(&self, time_start: Float, time_end: Float) -> Aabb {
+        let b = self.object.bounding_box(time_start, time_end);
+        let mut bounds = Aabb::EMPTY;
+        for i in 0..8 {
+            let corner = Vec3::new(
+                if i & 1 == 0 { b.min.x } else { b.max.x },
+                if i & 2 == 0 { b.min.y } else { b.max.y },
+                if i & 4 == 0 { b.min.z } else { b.max.z },
+            );
+            bounds = bounds.union_point(Self::point_to(&self.to_world, corner));
+        }
+        bounds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hit::rect::{Plane, Rect},
+        mat::{lambertian::Lambertian, Material, Materials},
+        rng,
+        tex::Texture,
+    };
+
+    #[test]
+    fn normals_transform_by_the_inverse_transpose() {
+        let material = Materials::new().add(Material::Lambertian(Lambertian {
+            albedo: Texture::Solid(Vec3::zero()),
+        }));
+        let square = Arc::new(Hittable::Rect(Rect::new(
+            Plane::Xy,
+            (-1.0, -1.0),
+            (1.0, 1.0),
+            0.0,
+            material,
+        )));
+        // Shears z by x, tilting the square onto the plane z = x. The columns are given in turn.
+        #[rustfmt::skip]
+        let shear = Matrix4::new(
+            1.0, 0.0, 1.0, 0.0,
+            0.0, 1.0, 0.0, 0.0,
+            0.0, 0.0, 1.0, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        );
+        let transform = Transform::from_matrix(square, shear).unwrap();
+
+        let ray = Ray {
+            origin: Vec3::new(0.5, 0.0, 5.0),
+            direction: Vec3::new(0.0, 0.0, -1.0),
+            time: 0.0,
+        };
+        let hit = transform
+            .hit(&ray, 0.001, Float::INFINITY, &mut rng::scene_rng(1))
+            .unwrap();
+        assert!(
+            (hit.p - Vec3::new(0.5, 0.0, 0.5)).length() < 1e-4,
+            "{}",
+            hit.p
+        );
+        let expected = Vec3::new(-1.0, 0.0, 1.0).unit_vec();
+        assert!((hit.normal - expected).length() < 1e-4, "{}", hit.normal);
+    }
+}
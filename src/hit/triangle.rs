@@ -0,0 +1,130 @@
+use std::sync::Arc;
+
+use cgmath::InnerSpace;
+use rand::Rng;
+
+use crate::{mat::MaterialId, math::Float, ray::Ray, Vec3};
+
+use super::{aabb::Aabb, light, HitRecord};
+
+/// Vertex data shared by all the triangles of a mesh. `normals` and `uvs` are either empty or
+/// the same length as `positions`.
+pub struct Mesh {
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>,
+    pub uvs: Vec<(Float, Float)>,
+}
+
+#[derive(Clone)]
+pub struct Triangle {
+    pub mesh: Arc<Mesh>,
+    pub indices: [usize; 3],
+    pub material: MaterialId,
+}
+
+impl Triangle {
+    #[inline]
+    fn vertices(&self) -> [Vec3; 3] {
+        self.indices.map(|i| self.mesh.positions[i])
+    }
+
+    /// Möller–Trumbore intersection.
+    #[inline]
+    pub fn hit(&self, ray: &Ray, t_min: Float, t_max: Float) -> Option<HitRecord> {
+        let [p0, p1, p2] = self.vertices();
+        let edge1 = p1 - p0;
+        let edge2 = p2 - p0;
+
+        let pvec: Vec3 = ray.direction.cross(edge2.0).into();
+        let det = edge1.dot(pvec.0);
+        // The ray runs parallel to the triangle, to within rounding. `det` scales with the ray's
+        // direction and both edges, so the cutoff does too, to suit meshes of any size.
+        let scale =
+            ray.direction.length_squared() * edge1.length_squared() * edge2.length_squared();
+        if det * det <= Float::EPSILON * Float::EPSILON * scale {
+            return None;
+        }
+        let inv_det = det.recip();
+
+        let tvec = ray.origin - p0;
+        let b1 = tvec.dot(pvec.0) * inv_det;
+        if !(0.0..=1.0).contains(&b1) {
+            return None;
+        }
+
+        let qvec: Vec3 = tvec.cross(edge1.0).into();
+        let b2 = ray.direction.dot(qvec.0) * inv_det;
+        if b2 < 0.0 || b1 + b2 > 1.0 {
+            return None;
+        }
+
+        let t = edge2.dot(qvec.0) * inv_det;
+        if t < t_min || t_max < t {
+            return None;
+        }
+
+        let b0 = 1.0 - b1 - b2;
+        let outward_normal = if self.mesh.normals.is_empty() {
+            Vec3::from(edge1.cross(edge2.0)).unit_vec()
+        } else {
+            let [n0, n1, n2] = self.indices.map(|i| self.mesh.normals[i]);
+            (b0 * n0 + b1 * n1 + b2 * n2).unit_vec()
+        };
+        let (front_face, normal) = HitRecord::face_and_normal(ray, outward_normal);
+
+        // Without texture coordinates, fall back on the barycentric coordinates
+        let (u, v) = if self.mesh.uvs.is_empty() {
+            (b1, b2)
+        } else {
+            let [t0, t1, t2] = self.indices.map(|i| self.mesh.uvs[i]);
+            (
+                b0 * t0.0 + b1 * t1.0 + b2 * t2.0,
+                b0 * t0.1 + b1 * t1.1 + b2 * t2.1,
+            )
+        };
+
+        Some(HitRecord {
+            p: ray.at(t),
+            normal,
+            t,
+            u,
+            v,
+            front_face,
+            material: self.material,
+        })
+    }
+
+    /// Probability density, over solid angle, of `random_towards` picking `direction` from
+    /// `origin`.
+    pub fn pdf_value(&self, origin: Vec3, direction: Vec3) -> Float {
+        let ray = Ray {
+            origin,
+            direction,
+            time: 0.0,
+        };
+        match self.hit(&ray, 0.001, Float::INFINITY) {
+            Some(rec) => {
+                // The geometric normal, as interpolated normals don't describe the area
+                let [p0, p1, p2] = self.vertices();
+                let n = Vec3::from((p1 - p0).cross((p2 - p0).0));
+                let area = 0.5 * n.length();
+                light::planar_pdf(direction, rec.t, n.unit_vec(), area)
+            }
+            None => 0.0,
+        }
+    }
+
+    /// A direction from `origin` towards a point chosen uniformly on the triangle.
+    pub fn random_towards(&self, origin: Vec3, rng: &mut impl Rng) -> Vec3 {
+        let [p0, p1, p2] = self.vertices();
+        let (r1, r2): (Float, Float) = rng.gen();
+        let s = r1.sqrt();
+        (1.0 - s) * p0 + (s * (1.0 - r2)) * p1 + (s * r2) * p2 - origin
+    }
+
+    #[inline]
+    pub fn bounding_box(&self) -> Aabb {
+        let [p0, p1, p2] = self.vertices();
+        Aabb::new(p0, p1).union_point(p2)
+    }
+}
@@ -0,0 +1,35 @@
+//! A raytracer, following the Ray Tracing in One Weekend series.
+//!
+//! Load or build a [`scene::Scene`], hand it to a [`Renderer`] with its
+//! [`scene::RenderSettings`], and save the [`Framebuffer`] that comes back.
+//!
+//! The `f32` feature changes [`math::Float`], and with it every position, direction and colour in
+//! the API, from `f64` to `f32`. Unlike most features it isn't additive: if two crates in a build
+//! disagree about it, one of them won't compile, so it's best left for the final program to turn
+//! on. Checkpoints record which precision they were rendered in, and can only be carried on in
+//! the same one.
+
+pub mod adaptive;
+pub mod camera;
+pub mod checkpoint;
+pub mod error;
+pub mod framebuffer;
+pub mod hit;
+pub mod mat;
+pub mod math;
+pub mod obj;
+pub mod output;
+pub mod pdf;
+pub mod ray;
+pub mod render;
+pub mod rng;
+pub mod scene;
+pub mod tex;
+pub mod tiles;
+pub mod tonemap;
+pub mod wavefront;
+
+pub use error::{Error, Result};
+pub use framebuffer::Framebuffer;
+pub use math::Vec3;
+pub use render::Renderer;
@@ -1,216 +1,201 @@
-mod camera;
-mod hit;
-mod mat;
-mod math;
-mod ray;
+mod cli;
 
-use std::f64;
-use std::sync::Arc;
+use std::{fmt, fs::File, io::Write, time::Instant};
 
-use cgmath::Deg;
-use rand::prelude::*;
+use clap::Parser;
+use indicatif::{ProgressBar, ProgressState, ProgressStyle};
+use rand::Rng;
 
-// .into_par_iter()  is often commented out for profiling, so this will scream
-#[allow(unused_imports)]
-use rayon::prelude::*;
+use raytracing_in_one_weekend::{
+    adaptive::Adaptive, checkpoint::Checkpoint, output::OutputFormat, rng, scene,
+    tonemap::ToneMapping, Error, Renderer, Result,
+};
 
-pub use math::Vec3;
-
-use camera::Camera;
-use hit::list::HittableList;
-use hit::sphere::Sphere;
-use hit::Hittable;
-use image::RgbImage;
-use mat::dielectric::Dielectric;
-use mat::lambertian::Lambertian;
-use mat::metal::Metal;
-use mat::Material;
-use ray::Ray;
-
-use crate::hit::moving_sphere::MovingSphere;
-
-const IMAGE_WIDTH: u32 = 1920;
-const IMAGE_HEIGHT: u32 = 1080;
-const ASPECT_RATIO: f64 = 1.777_777_777_777_777_7;
-
-const SAMPLES_PER_PIXEL: usize = 100;
-const MAX_RAY_DEPTH: i32 = 50;
+use cli::Args;
 
 fn main() {
-    let mut pixels = ndarray::Array2::<Vec3>::zeros((IMAGE_WIDTH as usize, IMAGE_HEIGHT as usize));
-
-    let world = random_scene();
-
-    const CAMERA_POS: Vec3 = Vec3::newi(13, 2, 3);
-    const CAMERA_TARGET: Vec3 = Vec3::newi(0, 0, 0);
-    const DIST_TO_FOCUS: f64 = 10.0;
-    const CAMERA_APERTURE: f64 = 0.1;
-
-    let camera = Camera::new(
-        CAMERA_POS,
-        CAMERA_TARGET,
-        Vec3::UNIT_UP,
-        Deg(20.0),
-        ASPECT_RATIO,
-        CAMERA_APERTURE,
-        DIST_TO_FOCUS,
-        0.0,
-        1.0,
-    );
-
-    let start = std::time::Instant::now();
-
-    pixels
-        .outer_iter_mut()
-        .into_par_iter()
-        .enumerate()
-        .for_each(|(row_number, mut row)| {
-            for (col_number, pixel) in row.iter_mut().enumerate() {
-                let mut colour = Vec3::zero();
-                for _ in 0..SAMPLES_PER_PIXEL {
-                    let vals: [f64; 2] = rand::thread_rng().gen();
-                    let u = (row_number as f64 + vals[0]) / (IMAGE_WIDTH as f64 - 1f64);
-                    let v = (col_number as f64 + vals[1]) / (IMAGE_HEIGHT as f64 - 1f64);
-                    let r = camera.get_ray(u, v);
-
-                    colour += ray_colour(&r, &world, MAX_RAY_DEPTH);
-                }
-
-                *pixel = colour;
-            }
-        });
+    if let Err(e) = run(Args::parse()) {
+        eprintln!("Error: {e}");
+        std::process::exit(1);
+    }
+}
 
-    println!("Took {:?}", start.elapsed());
+fn run(args: Args) -> Result<()> {
+    let format = args
+        .format
+        .or_else(|| OutputFormat::from_path(&args.output))
+        .ok_or_else(|| Error::UnknownFormat(args.output.clone()))?;
+
+    if let Some(threads) = args.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .map_err(|e| Error::ThreadPool(threads, e))?;
+    }
 
-    let mut image = RgbImage::new(IMAGE_WIDTH, IMAGE_HEIGHT);
+    let resumed = args.resume.as_deref().map(Checkpoint::load).transpose()?;
+    if let (Some(checkpoint), Some(path)) = (&resumed, &args.resume) {
+        let (width, height) = (
+            checkpoint.framebuffer.width(),
+            checkpoint.framebuffer.height(),
+        );
+        let mismatched = args.scene.as_ref().is_some_and(|s| *s != checkpoint.scene)
+            || args.seed.is_some_and(|s| s != checkpoint.seed)
+            || args.max_depth.is_some_and(|d| d != checkpoint.max_depth)
+            || args.width.is_some_and(|w| w != width)
+            || args.height.is_some_and(|h| h != height);
+        if mismatched {
+            return Err(Error::CheckpointSettings {
+                path: path.clone(),
+                scene: checkpoint.scene.clone(),
+                width,
+                height,
+                seed: checkpoint.seed,
+                max_depth: checkpoint.max_depth,
+            });
+        }
+    }
 
-    for j in 0..IMAGE_HEIGHT {
-        for i in 0..IMAGE_WIDTH {
-            let pix = pixels.get((i as usize, j as usize)).unwrap();
-            image.put_pixel(i, IMAGE_HEIGHT - 1 - j, pix.to_pixel(SAMPLES_PER_PIXEL));
+    let scene_name = match &resumed {
+        Some(checkpoint) => checkpoint.scene.clone(),
+        None => args.scene.unwrap_or_else(|| "random".to_owned()),
+    };
+    let scene_hash = scene::fingerprint(&scene_name)?;
+    if let (Some(checkpoint), Some(path)) = (&resumed, &args.resume) {
+        if checkpoint.scene_hash != scene_hash {
+            return Err(Error::SceneChanged(path.clone(), scene_name));
         }
     }
+    let seed = match &resumed {
+        Some(checkpoint) => checkpoint.seed,
+        None => args.seed.unwrap_or_else(|| rand::thread_rng().gen()),
+    };
+    println!("Seed: {seed}");
+    let mut rng = rng::scene_rng(seed);
+
+    let (scene, mut settings) = scene::load(&scene_name, &mut rng)?;
+    settings.width = args.width.unwrap_or(settings.width);
+    settings.height = args.height.unwrap_or(settings.height);
+    settings.samples_per_pixel = args.samples.unwrap_or(settings.samples_per_pixel);
+    settings.max_depth = args.max_depth.unwrap_or(settings.max_depth);
+    if let Some(checkpoint) = &resumed {
+        settings.width = checkpoint.framebuffer.width();
+        settings.height = checkpoint.framebuffer.height();
+        settings.max_depth = checkpoint.max_depth;
+    }
 
-    image.save("image.png").unwrap();
-}
+    println!(
+        "Scene: {} objects and {} materials within {}, BVH of {} nodes ({} leaves), depth {}",
+        scene.world.object_count(),
+        scene.materials.len(),
+        scene.world.bounding_box(),
+        scene.world.node_count(),
+        scene.world.leaf_count(),
+        scene.world.depth(),
+    );
 
-fn ray_colour(ray: &Ray, world: &HittableList, depth: i32) -> Vec3 {
-    if depth == 0 {
-        Vec3::zero()
-    } else {
-        match world.hit(ray, 0.001, f64::INFINITY) {
-            Some(hit) => {
-                let (attenuation, scattered) = hit.material.scatter(ray, &hit);
-                let r = ray_colour(&scattered, world, depth - 1);
-                attenuation * r
-            }
-            None => {
-                let unit_dir = ray.direction.unit_vec();
-                let t = 0.5 * (unit_dir.y + 1.0);
-                (1.0 - t) * Vec3::one() + t * Vec3::new(0.4, 0.7, 1.0)
-            }
-        }
+    let tone_mapping = ToneMapping {
+        operator: args.tone_map,
+        exposure: args.exposure,
+        white_point: args.white_point,
+    };
+    let checkpoint_path = args.checkpoint.or(args.resume);
+
+    let target = settings.samples_per_pixel as u32;
+    let mut renderer = Renderer::new(&scene, settings, seed)
+        .with_tiles(args.tile_size, args.tile_order)
+        .with_integrator(args.integrator)
+        .with_pass_samples(args.pass_samples.map_or(target, |samples| samples as u32));
+    if let Some(threshold) = args.adaptive_threshold {
+        renderer = renderer.with_adaptive(Adaptive {
+            threshold,
+            max_samples: args.max_samples.map_or(8 * target, |max| max as u32),
+        });
     }
-}
 
-#[macro_export]
-macro_rules! rand_f64 {
-    () => {{
-        use rand::Rng;
-        rand::thread_rng().gen_range(0.0..=1.0)
-    }};
-    ($beg:expr, $end:expr) => {{
-        use rand::Rng;
-        rand::thread_rng().gen_range($beg..$end)
-    }};
-    ($rng:expr) => {{
-        use rand::Rng;
-        rand::thread_rng().gen_range($rng)
-    }};
-}
+    let mut checkpoint = resumed.unwrap_or_else(|| Checkpoint {
+        scene: scene_name,
+        scene_hash,
+        seed,
+        max_depth: renderer.settings().max_depth,
+        framebuffer: renderer.framebuffer(),
+    });
+
+    let start = Instant::now();
+
+    let mut tile_times = args
+        .tile_times
+        .as_ref()
+        .map(|path| {
+            File::create(path)
+                .and_then(|mut file| {
+                    writeln!(file, "pass,x,y,width,height,samples,seconds")?;
+                    Ok((path, file))
+                })
+                .map_err(|e| Error::Io(path.clone(), e))
+        })
+        .transpose()?;
+
+    // Always writes the image at least once, so that resuming a finished render still does
+    let mut passes = 0;
+    loop {
+        let progress = ProgressBar::new(renderer.planned(&checkpoint.framebuffer))
+            .with_style(
+                ProgressStyle::with_template("{msg} [{wide_bar}] {percent:>3}% {rate} ETA {eta}")
+                    .unwrap()
+                    .with_key("rate", |state: &ProgressState, w: &mut dyn fmt::Write| {
+                        write!(w, "{:>8.0} samples/s", state.per_sec()).unwrap()
+                    }),
+            )
+            .with_message(format!("Pass {}", passes + 1));
+        let times = renderer.render_pass(&mut checkpoint.framebuffer, |n| progress.inc(n));
+        progress.finish_and_clear();
+
+        let taken: u64 = times.iter().map(|t| t.samples).sum();
+        if taken == 0 && passes > 0 {
+            break;
+        }
+        passes += 1;
+        let fb = &checkpoint.framebuffer;
+        if taken > 0 {
+            println!(
+                "Pass {passes}: {taken} samples, {:.1} per pixel on average, after {:?}",
+                fb.total_samples() as f64 / (fb.width() * fb.height()) as f64,
+                start.elapsed()
+            );
+        }
 
-fn random_scene() -> HittableList {
-    let mut world = HittableList(Vec::new());
+        if let Some((path, file)) = &mut tile_times {
+            times
+                .iter()
+                .try_for_each(|t| {
+                    writeln!(
+                        file,
+                        "{passes},{},{},{},{},{},{}",
+                        t.tile.x,
+                        t.tile.y,
+                        t.tile.width,
+                        t.tile.height,
+                        t.samples,
+                        t.time.as_secs_f64()
+                    )
+                })
+                .map_err(|e| Error::Io(path.to_path_buf(), e))?;
+        }
 
-    const GROUND_MATERIAL: Lambertian = Lambertian {
-        albedo: Vec3::new(0.5, 0.5, 0.5),
-    };
-    const GLASS_MATERIAL: Dielectric = Dielectric { ir: 1.5 };
-    const SPHERE_2_MATERIAL: Lambertian = Lambertian {
-        albedo: Vec3::new(0.4, 0.2, 0.1),
-    };
-    const SPHERE_3_MATERIAL: Metal = Metal {
-        albedo: Vec3::new(0.7, 0.6, 0.5),
-        fuzz: 0.0,
-    };
+        fb.save(&args.output, format, &tone_mapping)?;
+        if let Some(path) = &args.sample_map {
+            fb.save_sample_map(path)?;
+        }
+        if let Some(path) = &checkpoint_path {
+            checkpoint.save(path)?;
+        }
 
-    world.0.push(Hittable::Sphere(Sphere {
-        centre: Vec3::newi(0, -1000, 0),
-        radius: 1000.0,
-        material: Material::Lambertian(Arc::new(GROUND_MATERIAL)),
-    }));
-
-    for a in -11..11 {
-        for b in -11..11 {
-            let a = a as f64;
-            let b = b as f64;
-            let choose_mat = rand_f64!();
-            let centre = Vec3::new(a + 0.9 * rand_f64!(), 0.2, b + 0.9 * rand_f64!());
-            let radius = 0.2;
-
-            if (centre - Vec3::new(4.0, 0.2, 0.0)).length() > 0.9 {
-                if choose_mat < 0.8 {
-                    //diffuse
-                    let albedo = Vec3::random() * Vec3::random();
-                    let sphere_material = Lambertian { albedo };
-                    let centre_2 = centre + Vec3::new(0.0, rand_f64!(0.0, 0.5), 0.0);
-                    world.0.push(Hittable::MovingSphere(MovingSphere {
-                        centre_start: centre,
-                        centre_end: centre_2,
-                        radius,
-                        material: Material::Lambertian(Arc::new(sphere_material)),
-                        time_start: 0.0,
-                        time_end: 1.0,
-                    }))
-                } else if choose_mat < 0.95 {
-                    //metal
-                    let albedo = Vec3::random_with_range(0.5, 1.0);
-                    let fuzz = rand_f64!(0.0..0.5);
-                    let sphere_material = Metal { albedo, fuzz };
-                    world.0.push(Hittable::Sphere(Sphere {
-                        centre,
-                        radius,
-                        material: Material::Metal(Arc::new(sphere_material)),
-                    }))
-                } else {
-                    //glass
-                    world.0.push(Hittable::Sphere(Sphere {
-                        centre,
-                        radius,
-                        material: Material::Dielectric(GLASS_MATERIAL),
-                    }))
-                }
-            }
+        if taken == 0 {
+            break;
         }
     }
 
-    world.0.push(Hittable::Sphere(Sphere {
-        centre: Vec3::newi(0, 1, 0),
-        radius: 1.0,
-        material: Material::Dielectric(GLASS_MATERIAL),
-    }));
-
-    world.0.push(Hittable::Sphere(Sphere {
-        centre: Vec3::newi(-4, 1, 0),
-        radius: 1.0,
-        material: Material::Lambertian(Arc::new(SPHERE_2_MATERIAL)),
-    }));
-
-    world.0.push(Hittable::Sphere(Sphere {
-        centre: Vec3::newi(4, 1, 0),
-        radius: 1.0,
-        material: Material::Metal(Arc::new(SPHERE_3_MATERIAL)),
-    }));
-
-    world
+    println!("Took {:?}", start.elapsed());
+    Ok(())
 }
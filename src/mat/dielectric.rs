@@ -1,16 +1,24 @@
 use cgmath::InnerSpace;
+use rand::Rng;
 
-use crate::{hit::HitRecord, math, rand_f64, ray::Ray, Vec3};
+use crate::{
+    hit::HitRecord,
+    math::{self, Float},
+    ray::Ray,
+    Vec3,
+};
+
+use super::Scatter;
 
 #[repr(transparent)]
 #[derive(Copy, Clone)]
 pub struct Dielectric {
-    pub ir: f64,
+    pub ir: Float,
 }
 
 impl Dielectric {
     #[inline]
-    pub fn scatter(&self, ray: &Ray, rec: &HitRecord) -> (Vec3, Ray) {
+    pub fn sample(&self, ray: &Ray, rec: &HitRecord, rng: &mut impl Rng) -> Scatter {
         let attenuation = Vec3::one();
         let refraction_ratio = if rec.front_face {
             self.ir.recip()
@@ -25,20 +33,20 @@ impl Dielectric {
         let cannot_refract = (refraction_ratio * sin_theta) > 1.0;
 
         let direction = if cannot_refract
-            || math::shlick_reflectance(cos_theta, refraction_ratio) > rand_f64!()
+            || math::shlick_reflectance(cos_theta, refraction_ratio) > rng.gen()
         {
             unit_dir.reflect(&rec.normal)
         } else {
             unit_dir.refract(&rec.normal, refraction_ratio)
         };
 
-        (
+        Scatter::Specular {
             attenuation,
-            Ray {
+            ray: Ray {
                 origin: rec.p,
                 direction,
                 time: ray.time,
             },
-        )
+        }
     }
 }
@@ -0,0 +1,12 @@
+use crate::{hit::HitRecord, tex::Texture, Vec3};
+
+pub struct DiffuseLight {
+    pub emit: Texture,
+}
+
+impl DiffuseLight {
+    #[inline(always)]
+    pub fn emitted(&self, rec: &HitRecord) -> Vec3 {
+        self.emit.value(rec.u, rec.v, &rec.p)
+    }
+}
@@ -0,0 +1,33 @@
+use crate::{
+    hit::HitRecord,
+    math::{consts::PI, Float},
+    pdf::Pdf,
+    tex::Texture,
+    Vec3,
+};
+
+use super::Scatter;
+
+/// The phase function of a participating medium that scatters light equally in every direction.
+#[repr(transparent)]
+pub struct Isotropic {
+    pub albedo: Texture,
+}
+
+impl Isotropic {
+    #[inline(always)]
+    pub fn sample(&self) -> Scatter {
+        Scatter::Diffuse(Pdf::Sphere)
+    }
+
+    /// The same whichever way the light arrives from.
+    #[inline(always)]
+    pub fn eval(&self, rec: &HitRecord) -> Vec3 {
+        self.albedo.value(rec.u, rec.v, &rec.p) * self.pdf()
+    }
+
+    #[inline(always)]
+    pub fn pdf(&self) -> Float {
+        0.25 / PI
+    }
+}
@@ -1,26 +1,36 @@
-use crate::{hit::HitRecord, ray::Ray, Vec3};
+use cgmath::InnerSpace;
+
+use crate::{
+    hit::HitRecord,
+    math::{consts::FRAC_1_PI, Float, Onb},
+    pdf::Pdf,
+    tex::Texture,
+    Vec3,
+};
+
+use super::Scatter;
 
 #[repr(transparent)]
 pub struct Lambertian {
-    pub albedo: Vec3,
+    pub albedo: Texture,
 }
 
 impl Lambertian {
+    /// Directions are cosine-weighted, which cancels the cosine term out of the BSDF.
     #[inline(always)]
-    pub fn scatter(&self, ray: &Ray, rec: &HitRecord) -> (Vec3, Ray) {
-        //let mut scatter_direction = rec.normal + Vec3::random_unit_vector();
-        //let mut scatter_direction = (rec.p + rec.normal + Vec3::random_in_unit_sphere()) - rec.p;
-        let mut scatter_direction = (rec.p + Vec3::random_in_hemisphere(rec.normal)) - rec.p;
+    pub fn sample(&self, rec: &HitRecord) -> Scatter {
+        Scatter::Diffuse(Pdf::Cosine(Onb::from_w(rec.normal)))
+    }
 
-        if scatter_direction.is_near_zero() {
-            scatter_direction = rec.normal;
-        }
+    #[inline(always)]
+    pub fn eval(&self, rec: &HitRecord, direction: Vec3) -> Vec3 {
+        let cosine = rec.normal.dot(direction.unit_vec().0).max(0.0);
+        self.albedo.value(rec.u, rec.v, &rec.p) * (cosine * FRAC_1_PI)
+    }
 
-        let scattered = Ray {
-            origin: rec.p,
-            direction: scatter_direction,
-            time: ray.time,
-        };
-        (self.albedo, scattered)
+    #[inline(always)]
+    pub fn pdf(&self, rec: &HitRecord, direction: Vec3) -> Float {
+        let cosine = rec.normal.dot(direction.unit_vec().0);
+        (cosine * FRAC_1_PI).max(0.0)
     }
 }
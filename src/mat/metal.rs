@@ -1,19 +1,28 @@
-use crate::{hit::HitRecord, ray::Ray, Vec3};
+use rand::Rng;
+
+use crate::{hit::HitRecord, math::Float, ray::Ray, tex::Texture, Vec3};
+
+use super::Scatter;
 
 pub struct Metal {
-    pub albedo: Vec3,
-    pub fuzz: f64,
+    pub albedo: Texture,
+    pub fuzz: Float,
 }
 
 impl Metal {
     #[inline(always)]
-    pub fn scatter(&self, ray: &Ray, rec: &HitRecord) -> (Vec3, Ray) {
+    pub fn sample(&self, ray: &Ray, rec: &HitRecord, rng: &mut impl Rng) -> Scatter {
         let reflected = ray.direction.unit_vec().reflect(&rec.normal);
         let scattered = Ray {
             origin: rec.p,
-            direction: reflected + self.fuzz * Vec3::random_in_unit_sphere(),
+            direction: reflected + self.fuzz * Vec3::random_in_unit_sphere(rng),
             time: ray.time,
         };
-        (self.albedo, scattered)
+        // Fuzzy reflections aren't truly specular, but are close enough that light sampling
+        // would rarely help
+        Scatter::Specular {
+            attenuation: self.albedo.value(rec.u, rec.v, &rec.p),
+            ray: scattered,
+        }
     }
 }
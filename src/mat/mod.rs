@@ -1,27 +1,148 @@
 pub mod dielectric;
+pub mod diffuse_light;
+pub mod isotropic;
 pub mod lambertian;
 pub mod metal;
 
-use std::sync::Arc;
+use std::ops::Index;
 
-use crate::{hit::HitRecord, ray::Ray, Vec3};
+use rand::{Rng, RngCore};
 
-use self::{dielectric::Dielectric, lambertian::Lambertian, metal::Metal};
+use crate::{hit::HitRecord, math::Float, pdf::Pdf, ray::Ray, Vec3};
+
+use self::{
+    dielectric::Dielectric, diffuse_light::DiffuseLight, isotropic::Isotropic,
+    lambertian::Lambertian, metal::Metal,
+};
+
+/// Where a material sends a ray that hits it.
+pub enum Scatter {
+    /// Along exactly `ray`, which light sampling could never pick, letting `attenuation` of the
+    /// light coming back along it through.
+    Specular { attenuation: Vec3, ray: Ray },
+    /// In a direction drawn from the PDF, letting `Material::eval` over its density through.
+    Diffuse(Pdf<'static>),
+}
+
+/// A material from outside this crate, added to a scene as a `Material::Custom`. The built-in
+/// materials are matched on directly instead, which is faster.
+///
+/// Only materials that scatter with `Scatter::Diffuse` need `eval` and `pdf`, and only emitters
+/// need `emitted`.
+pub trait Bsdf: Send + Sync {
+    /// `None` for materials that only emit.
+    fn sample(&self, ray: &Ray, rec: &HitRecord, rng: &mut dyn RngCore) -> Option<Scatter>;
+
+    /// The BSDF times the cosine term for light arriving from `direction` and leaving back the
+    /// way the hit came in.
+    fn eval(&self, _rec: &HitRecord, _direction: Vec3) -> Vec3 {
+        Vec3::zero()
+    }
+
+    /// Density over solid angle of the PDF from `sample` picking `direction`.
+    fn pdf(&self, _rec: &HitRecord, _direction: Vec3) -> Float {
+        0.0
+    }
+
+    fn emitted(&self, _rec: &HitRecord) -> Vec3 {
+        Vec3::zero()
+    }
+}
 
-#[derive(Clone)]
 pub enum Material {
-    Lambertian(Arc<Lambertian>),
-    Metal(Arc<Metal>),
+    Lambertian(Lambertian),
+    Metal(Metal),
     Dielectric(Dielectric),
+    DiffuseLight(DiffuseLight),
+    Isotropic(Isotropic),
+    Custom(Box<dyn Bsdf>),
+}
+
+/// Refers to a material in a scene's `Materials`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct MaterialId(u32);
+
+/// Every material in a scene, each stored once, for objects and hits to refer to by id.
+#[derive(Default)]
+pub struct Materials(Vec<Material>);
+
+impl Materials {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, material: Material) -> MaterialId {
+        let id = MaterialId(self.0.len() as u32);
+        self.0.push(material);
+        id
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (MaterialId, &Material)> {
+        (0..).map(MaterialId).zip(&self.0)
+    }
+}
+
+impl Index<MaterialId> for Materials {
+    type Output = Material;
+
+    #[inline]
+    fn index(&self, id: MaterialId) -> &Material {
+        &self.0[id.0 as usize]
+    }
 }
 
 impl Material {
+    /// `None` for materials that only emit.
+    #[inline]
+    pub fn sample(&self, ray: &Ray, rec: &HitRecord, rng: &mut impl Rng) -> Option<Scatter> {
+        match self {
+            Material::Lambertian(l) => Some(l.sample(rec)),
+            Material::Metal(m) => Some(m.sample(ray, rec, rng)),
+            Material::Dielectric(d) => Some(d.sample(ray, rec, rng)),
+            Material::DiffuseLight(_) => None,
+            Material::Isotropic(i) => Some(i.sample()),
+            Material::Custom(bsdf) => bsdf.sample(ray, rec, rng),
+        }
+    }
+
+    /// The BSDF times the cosine term for light arriving from `direction` and leaving back the way
+    /// the hit came in. Zero for specular materials and lights.
+    #[inline]
+    pub fn eval(&self, rec: &HitRecord, direction: Vec3) -> Vec3 {
+        match self {
+            Material::Lambertian(l) => l.eval(rec, direction),
+            Material::Isotropic(i) => i.eval(rec),
+            Material::Custom(bsdf) => bsdf.eval(rec, direction),
+            _ => Vec3::zero(),
+        }
+    }
+
+    /// Density over solid angle of the PDF from `sample` picking `direction`. Zero for specular
+    /// materials and lights.
+    #[inline]
+    pub fn pdf(&self, rec: &HitRecord, direction: Vec3) -> Float {
+        match self {
+            Material::Lambertian(l) => l.pdf(rec, direction),
+            Material::Isotropic(i) => i.pdf(),
+            Material::Custom(bsdf) => bsdf.pdf(rec, direction),
+            _ => 0.0,
+        }
+    }
+
     #[inline]
-    pub fn scatter(&self, ray: &Ray, rec: &HitRecord) -> (Vec3, Ray) {
+    pub fn emitted(&self, rec: &HitRecord) -> Vec3 {
         match self {
-            Material::Lambertian(l) => l.scatter(ray, rec),
-            Material::Metal(m) => m.scatter(ray, rec),
-            Material::Dielectric(d) => d.scatter(ray, rec),
+            Material::DiffuseLight(l) => l.emitted(rec),
+            Material::Custom(bsdf) => bsdf.emitted(rec),
+            _ => Vec3::zero(),
         }
     }
 }
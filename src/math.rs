@@ -4,25 +4,47 @@ use std::{
 };
 
 use cgmath::{prelude::*, AbsDiffEq, Vector3};
-use image::Rgb;
 use rand::{distributions::Uniform, prelude::*};
 
+use self::consts::PI;
+
+/// The precision of everything geometric: positions, directions, colours and the maths between
+/// them. `f64` unless the `f32` feature is on, which halves the memory and bandwidth big meshes
+/// take, at the cost of accuracy in scenes with very large or very distant objects.
+#[cfg(not(feature = "f32"))]
+pub type Float = f64;
+#[cfg(feature = "f32")]
+pub type Float = f32;
+
+#[cfg(feature = "f32")]
+pub use std::f32::consts;
+#[cfg(not(feature = "f32"))]
+pub use std::f64::consts;
+
+/// Widens a `Float` to `f64`, for the statistics and file formats that are the same whichever
+/// precision the geometry is in.
+#[inline]
+#[allow(clippy::unnecessary_cast)]
+pub fn wide(x: Float) -> f64 {
+    x as f64
+}
+
 #[repr(transparent)]
 #[derive(Clone, Copy, PartialEq)]
-pub struct Vec3(pub Vector3<f64>);
+pub struct Vec3(pub Vector3<Float>);
 
 impl Vec3 {
     pub const UNIT_UP: Vec3 = Vec3::newi(0, 1, 0);
 
-    pub const fn new(x: f64, y: f64, z: f64) -> Self {
+    pub const fn new(x: Float, y: Float, z: Float) -> Self {
         Self(Vector3 { x, y, z })
     }
 
     pub const fn newi(x: i32, y: i32, z: i32) -> Self {
         Self(Vector3 {
-            x: x as f64,
-            y: y as f64,
-            z: z as f64,
+            x: x as Float,
+            y: y as Float,
+            z: z as Float,
         })
     }
 
@@ -34,27 +56,25 @@ impl Vec3 {
         Self::new(1.0, 1.0, 1.0)
     }
 
-    pub fn random() -> Self {
-        Self::new(crate::rand_f64!(), crate::rand_f64!(), crate::rand_f64!())
+    pub fn random(rng: &mut impl Rng) -> Self {
+        Self::new(rng.gen(), rng.gen(), rng.gen())
     }
 
     #[inline]
-    pub fn random_with_range(min: f64, max: f64) -> Self {
+    pub fn random_with_range(rng: &mut impl Rng, min: Float, max: Float) -> Self {
         let u = Uniform::new(min, max);
-        let mut r = rand::thread_rng();
-        Self::new(u.sample(&mut r), u.sample(&mut r), u.sample(&mut r))
+        Self::new(u.sample(rng), u.sample(rng), u.sample(rng))
     }
     #[inline]
-    pub fn random_xy_with_range(min: f64, max: f64) -> Self {
+    pub fn random_xy_with_range(rng: &mut impl Rng, min: Float, max: Float) -> Self {
         let u = Uniform::new(min, max);
-        let mut r = rand::thread_rng();
-        Self::new(u.sample(&mut r), u.sample(&mut r), 0.0)
+        Self::new(u.sample(rng), u.sample(rng), 0.0)
     }
 
     #[inline]
-    pub fn random_in_unit_sphere() -> Self {
+    pub fn random_in_unit_sphere(rng: &mut impl Rng) -> Self {
         loop {
-            let p = Self::random_with_range(-1.0, 1.0);
+            let p = Self::random_with_range(rng, -1.0, 1.0);
             if p.length_squared() < 1.0 {
                 break p;
             }
@@ -62,9 +82,9 @@ impl Vec3 {
     }
 
     #[inline]
-    pub fn random_in_unit_disc() -> Self {
+    pub fn random_in_unit_disc(rng: &mut impl Rng) -> Self {
         loop {
-            let p = Self::random_xy_with_range(-1.0, 1.0);
+            let p = Self::random_xy_with_range(rng, -1.0, 1.0);
             if p.length_squared() < 1.0 {
                 break p;
             }
@@ -72,25 +92,24 @@ impl Vec3 {
     }
 
     #[inline]
-    pub fn random_unit_vector() -> Self {
-        Self::random_in_unit_sphere().unit_vec()
+    pub fn random_unit_vector(rng: &mut impl Rng) -> Self {
+        Self::random_in_unit_sphere(rng).unit_vec()
     }
 
+    /// A unit vector in the hemisphere about +z, with density proportional to its z component.
     #[inline]
-    pub fn random_in_hemisphere(normal: Self) -> Self {
-        let in_unit_sphere = Self::random_in_unit_sphere();
-        if in_unit_sphere.dot(normal.0).is_sign_positive() {
-            in_unit_sphere
-        } else {
-            -in_unit_sphere
-        }
+    pub fn random_cosine_direction(rng: &mut impl Rng) -> Self {
+        let (r1, r2): (Float, Float) = rng.gen();
+        let phi = 2.0 * PI * r1;
+        let r = r2.sqrt();
+        Self::new(phi.cos() * r, phi.sin() * r, (1.0 - r2).sqrt())
     }
 
-    pub fn length_squared(&self) -> f64 {
+    pub fn length_squared(&self) -> Float {
         self.magnitude2()
     }
 
-    pub fn length(&self) -> f64 {
+    pub fn length(&self) -> Float {
         self.magnitude()
     }
 
@@ -98,6 +117,18 @@ impl Vec3 {
         self / self.length()
     }
 
+    /// Relative luminance, taking the components as linear Rec. 709 RGB.
+    #[inline]
+    pub fn luminance(&self) -> Float {
+        0.2126 * self.x + 0.7152 * self.y + 0.0722 * self.z
+    }
+
+    /// The components widened to `f64`, for adding up samples without losing precision.
+    #[inline]
+    pub fn wide(&self) -> Vector3<f64> {
+        Vector3::new(wide(self.x), wide(self.y), wide(self.z))
+    }
+
     pub fn is_near_zero(&self) -> bool {
         self.0.abs_diff_eq(&Zero::zero(), 1e-8)
     }
@@ -106,20 +137,7 @@ impl Vec3 {
         self - &(2.0 * self.dot(normal.0) * normal)
     }
 
-    pub fn to_pixel(self, num_samples: usize) -> Rgb<u8> {
-        let scale = 1.0 / num_samples as f64;
-        let r = (self.x * scale).sqrt();
-        let g = (self.y * scale).sqrt();
-        let b = (self.z * scale).sqrt();
-
-        let r = (r.clamp(0.0, 0.999) * 256.0) as u8;
-        let g = (g.clamp(0.0, 0.999) * 256.0) as u8;
-        let b = (b.clamp(0.0, 0.999) * 256.0) as u8;
-
-        Rgb([r, g, b])
-    }
-
-    pub fn refract(&self, n: &Self, etai_over_etat: f64) -> Self {
+    pub fn refract(&self, n: &Self, etai_over_etat: Float) -> Self {
         let cos_theta = (-self).dot(n.0).min(1.0);
         let r_out_perp = etai_over_etat * (self + &(cos_theta * n));
         let r_out_parallel = -((1.0 - r_out_perp.length_squared()).abs()).sqrt() * n;
@@ -128,7 +146,7 @@ impl Vec3 {
 }
 
 impl Deref for Vec3 {
-    type Target = Vector3<f64>;
+    type Target = Vector3<Float>;
 
     #[inline]
     fn deref(&self) -> &Self::Target {
@@ -194,7 +212,7 @@ impl Sub for &Vec3 {
 
 impl<T> Mul<T> for Vec3
 where
-    T: Into<f64>,
+    T: Into<Float>,
 {
     type Output = Vec3;
 
@@ -206,7 +224,7 @@ where
 
 impl<T> Mul<T> for &Vec3
 where
-    T: Into<f64>,
+    T: Into<Float>,
 {
     type Output = Vec3;
 
@@ -216,7 +234,7 @@ where
     }
 }
 
-impl Mul<Vec3> for f64 {
+impl Mul<Vec3> for Float {
     type Output = Vec3;
 
     #[inline]
@@ -225,7 +243,7 @@ impl Mul<Vec3> for f64 {
     }
 }
 
-impl Mul<&Vec3> for f64 {
+impl Mul<&Vec3> for Float {
     type Output = Vec3;
 
     #[inline]
@@ -245,7 +263,7 @@ impl Mul for Vec3 {
 
 impl<T> MulAssign<T> for Vec3
 where
-    T: Into<f64>,
+    T: Into<Float>,
 {
     #[inline]
     fn mul_assign(&mut self, rhs: T) {
@@ -264,7 +282,7 @@ impl MulAssign for Vec3 {
 
 impl<T> Div<T> for Vec3
 where
-    T: Into<f64>,
+    T: Into<Float>,
 {
     type Output = Vec3;
 
@@ -276,7 +294,7 @@ where
 
 impl<T> Div<T> for &Vec3
 where
-    T: Into<f64>,
+    T: Into<Float>,
 {
     type Output = Vec3;
 
@@ -288,7 +306,7 @@ where
 
 impl<T> DivAssign<T> for Vec3
 where
-    T: Into<f64>,
+    T: Into<Float>,
 {
     #[inline]
     fn div_assign(&mut self, rhs: T) {
@@ -340,14 +358,43 @@ impl Zero for Vec3 {
     }
 }
 
-impl From<Vector3<f64>> for Vec3 {
-    fn from(x: Vector3<f64>) -> Self {
+impl From<Vector3<Float>> for Vec3 {
+    fn from(x: Vector3<Float>) -> Self {
         Self(x)
     }
 }
 
+/// An orthonormal basis with `w` pointing along a given direction, for turning directions sampled
+/// about the z axis into directions about that one.
+pub struct Onb {
+    pub u: Vec3,
+    pub v: Vec3,
+    pub w: Vec3,
+}
+
+impl Onb {
+    #[inline]
+    pub fn from_w(w: Vec3) -> Self {
+        let w = w.unit_vec();
+        let a = if w.x.abs() > 0.9 {
+            Vec3::UNIT_UP
+        } else {
+            Vec3::newi(1, 0, 0)
+        };
+        let v = Vec3(w.cross(a.0)).unit_vec();
+        let u = Vec3(w.cross(v.0));
+        Self { u, v, w }
+    }
+
+    /// Takes coordinates in this basis to world space.
+    #[inline]
+    pub fn local(&self, a: Vec3) -> Vec3 {
+        a.x * self.u + a.y * self.v + a.z * self.w
+    }
+}
+
 #[inline]
-pub fn shlick_reflectance(cosine: f64, refractive_index: f64) -> f64 {
+pub fn shlick_reflectance(cosine: Float, refractive_index: Float) -> Float {
     let r0 = ((1.0 - refractive_index) / (1.0 + refractive_index)).powi(2);
     r0 + (1.0 - r0) * (1.0 - cosine).powi(5)
 }
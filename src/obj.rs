@@ -0,0 +1,185 @@
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use cgmath::Zero;
+
+use crate::{
+    hit::{
+        list::HittableList,
+        triangle::{Mesh, Triangle},
+        Hittable,
+    },
+    mat::{
+        dielectric::Dielectric, diffuse_light::DiffuseLight, lambertian::Lambertian, metal::Metal,
+        Material, MaterialId, Materials,
+    },
+    math::Float,
+    tex::{image_texture::ImageTexture, Texture},
+    Vec3,
+};
+
+/// Something an OBJ file needs that couldn't be loaded.
+#[derive(Debug)]
+pub enum ObjError {
+    Obj(tobj::LoadError),
+    /// The file's MTL library.
+    Materials(tobj::LoadError),
+    /// A texture map named in the MTL library.
+    Texture(PathBuf, image::ImageError),
+}
+
+impl Display for ObjError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ObjError::Obj(e) => write!(f, "{e}"),
+            ObjError::Materials(e) => write!(f, "couldn't load its materials: {e}"),
+            ObjError::Texture(path, e) => {
+                write!(f, "couldn't load texture {}: {e}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ObjError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ObjError::Obj(e) | ObjError::Materials(e) => Some(e),
+            ObjError::Texture(_, e) => Some(e),
+        }
+    }
+}
+
+const DEFAULT_MATERIAL: Lambertian = Lambertian {
+    albedo: Texture::Solid(Vec3::new(0.5, 0.5, 0.5)),
+};
+
+/// Loads every model in a Wavefront OBJ file as triangles, scaled by `scale` about the origin and
+/// then moved by `offset`. Faces are given the material named by the preceding `usemtl`, looked
+/// up in the file's MTL library. Faces with no material are grey `Lambertian`s. Diffuse texture
+/// maps are loaded relative to the OBJ file. The materials are added to `materials`, once each
+/// however many faces use them.
+pub fn load_obj(
+    path: impl AsRef<Path>,
+    scale: Float,
+    offset: Vec3,
+    materials: &mut Materials,
+) -> Result<HittableList, ObjError> {
+    let (models, mtl_materials) = tobj::load_obj(
+        path.as_ref(),
+        &tobj::LoadOptions {
+            single_index: true,
+            triangulate: true,
+            ..Default::default()
+        },
+    )
+    .map_err(ObjError::Obj)?;
+
+    let base_dir = path.as_ref().parent().unwrap_or_else(|| Path::new(""));
+    let mtl_materials = mtl_materials
+        .map_err(ObjError::Materials)?
+        .iter()
+        .map(|m| Ok(materials.add(convert_material(m, base_dir)?)))
+        .collect::<Result<Vec<MaterialId>, _>>()?;
+    let mut default_material = None;
+
+    let mut list = HittableList(Vec::new());
+    for model in models {
+        let mesh = model.mesh;
+        let material = match mesh.material_id.and_then(|id| mtl_materials.get(id)) {
+            Some(&material) => material,
+            None => *default_material
+                .get_or_insert_with(|| materials.add(Material::Lambertian(DEFAULT_MATERIAL))),
+        };
+
+        let positions = mesh
+            .positions
+            .chunks_exact(3)
+            .map(|p| offset + scale * vec3(p))
+            .collect();
+        let normals = mesh.normals.chunks_exact(3).map(vec3).collect();
+        let uvs = mesh
+            .texcoords
+            .chunks_exact(2)
+            .map(|t| (t[0] as Float, t[1] as Float))
+            .collect();
+        let shared = Arc::new(Mesh {
+            positions,
+            normals,
+            uvs,
+        });
+
+        list.0.extend(mesh.indices.chunks_exact(3).map(|face| {
+            Hittable::Triangle(Triangle {
+                mesh: Arc::clone(&shared),
+                indices: [face[0] as usize, face[1] as usize, face[2] as usize],
+                material,
+            })
+        }));
+    }
+
+    Ok(list)
+}
+
+/// Picks whichever of our materials best approximates an MTL material, going by its emission,
+/// illumination model and transparency.
+fn convert_material(mtl: &tobj::Material, base_dir: &Path) -> Result<Material, ObjError> {
+    // tobj doesn't know about `Ke`, so it ends up with the unrecognised parameters
+    let emission = mtl.unknown_param.get("Ke").and_then(|ke| {
+        let c: Vec<Float> = ke
+            .split_whitespace()
+            .map(str::parse)
+            .collect::<Result<_, _>>()
+            .ok()?;
+        match c[..] {
+            [r, g, b] => Some(Vec3::new(r, g, b)),
+            _ => None,
+        }
+    });
+    if let Some(emit) = emission.filter(|e| !e.is_zero()) {
+        return Ok(Material::DiffuseLight(DiffuseLight {
+            emit: Texture::Solid(emit),
+        }));
+    }
+    // `Ni` defaults to 1, which would make glass with no `Ni` given invisible
+    let ir = if mtl.optical_density > 1.0 {
+        mtl.optical_density as Float
+    } else {
+        1.5
+    };
+
+    Ok(match mtl.illumination_model {
+        // Refraction, with or without ray-traced reflection
+        Some(4 | 6 | 7 | 9) => Material::Dielectric(Dielectric { ir }),
+        _ if mtl.dissolve < 1.0 => Material::Dielectric(Dielectric { ir }),
+        // Ray-traced reflection
+        Some(3 | 5 | 8) => Material::Metal(Metal {
+            albedo: Texture::Solid(vec3(&mtl.specular)),
+            // The Phong exponent runs from 0 to 1000, with higher values being glossier
+            fuzz: (1.0 - Float::from(mtl.shininess) / 1000.0).clamp(0.0, 1.0),
+        }),
+        _ => Material::Lambertian(Lambertian {
+            albedo: diffuse_texture(mtl, base_dir)?,
+        }),
+    })
+}
+
+/// The `map_Kd` image if there is one, otherwise the flat `Kd` colour.
+fn diffuse_texture(mtl: &tobj::Material, base_dir: &Path) -> Result<Texture, ObjError> {
+    if mtl.diffuse_texture.is_empty() {
+        return Ok(Texture::Solid(vec3(&mtl.diffuse)));
+    }
+    let path = base_dir.join(&mtl.diffuse_texture);
+    match ImageTexture::open(&path) {
+        Ok(image) => Ok(Texture::Image(Arc::new(image))),
+        Err(e) => Err(ObjError::Texture(path, e)),
+    }
+}
+
+/// Converts the first three of a run of OBJ coordinates or colour channels, which are always
+/// single precision.
+fn vec3(c: &[f32]) -> Vec3 {
+    Vec3::new(c[0] as Float, c[1] as Float, c[2] as Float)
+}
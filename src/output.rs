@@ -0,0 +1,139 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use clap::ValueEnum;
+use image::{codecs::hdr::HdrEncoder, ImageFormat, ImageResult, Rgb, Rgb32FImage, RgbImage};
+use ndarray::Array2;
+
+use crate::{tonemap::ToneMapping, Framebuffer};
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum OutputFormat {
+    Png,
+    Jpeg,
+    Bmp,
+    Tga,
+    Tiff,
+    Ppm,
+    /// OpenEXR, with linear 32-bit float channels
+    Exr,
+    /// Radiance RGBE, with linear channels
+    Hdr,
+    /// Portable float map, with linear 32-bit float channels
+    Pfm,
+}
+
+impl OutputFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        // The image crate doesn't know about PFM
+        if path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("pfm"))
+        {
+            return Some(OutputFormat::Pfm);
+        }
+
+        match ImageFormat::from_path(path).ok()? {
+            ImageFormat::Png => Some(OutputFormat::Png),
+            ImageFormat::Jpeg => Some(OutputFormat::Jpeg),
+            ImageFormat::Bmp => Some(OutputFormat::Bmp),
+            ImageFormat::Tga => Some(OutputFormat::Tga),
+            ImageFormat::Tiff => Some(OutputFormat::Tiff),
+            ImageFormat::Pnm => Some(OutputFormat::Ppm),
+            ImageFormat::OpenExr => Some(OutputFormat::Exr),
+            ImageFormat::Hdr => Some(OutputFormat::Hdr),
+            _ => None,
+        }
+    }
+}
+
+/// Writes out the mean of each pixel's samples. High dynamic range formats get the radiance
+/// linear and unclamped; everything else goes through `tone_mapping`.
+pub fn write_image(
+    fb: &Framebuffer,
+    path: &Path,
+    format: OutputFormat,
+    tone_mapping: &ToneMapping,
+) -> ImageResult<()> {
+    let ldr_format = match format {
+        OutputFormat::Exr => return linear_image(fb).save_with_format(path, ImageFormat::OpenExr),
+        OutputFormat::Hdr => {
+            let image = linear_image(fb);
+            let data: Vec<Rgb<f32>> = image.pixels().copied().collect();
+            let file = BufWriter::new(File::create(path)?);
+            return HdrEncoder::new(file).encode(
+                &data,
+                image.width() as usize,
+                image.height() as usize,
+            );
+        }
+        OutputFormat::Pfm => return write_pfm(fb, path),
+        OutputFormat::Png => ImageFormat::Png,
+        OutputFormat::Jpeg => ImageFormat::Jpeg,
+        OutputFormat::Bmp => ImageFormat::Bmp,
+        OutputFormat::Tga => ImageFormat::Tga,
+        OutputFormat::Tiff => ImageFormat::Tiff,
+        OutputFormat::Ppm => ImageFormat::Pnm,
+    };
+
+    let (width, height) = (fb.width(), fb.height());
+    let mut image = RgbImage::new(width, height);
+
+    for j in 0..height {
+        for i in 0..width {
+            let index = (i as usize, j as usize);
+            image.put_pixel(
+                i,
+                height - 1 - j,
+                tone_mapping.to_pixel(fb.sums[index], fb.counts[index].max(1) as usize),
+            );
+        }
+    }
+
+    image.save_with_format(path, ldr_format)
+}
+
+fn linear_image(fb: &Framebuffer) -> Rgb32FImage {
+    let (width, height) = (fb.width(), fb.height());
+
+    Rgb32FImage::from_fn(width, height, |i, j| {
+        let pix = fb.mean(i as usize, (height - 1 - j) as usize);
+        Rgb([pix.x as f32, pix.y as f32, pix.z as f32])
+    })
+}
+
+/// Portable float map. Unlike most formats, rows go from the bottom of the image to the top, and
+/// a negative scale marks the data as little-endian.
+fn write_pfm(fb: &Framebuffer, path: &Path) -> ImageResult<()> {
+    let (width, height) = (fb.width(), fb.height());
+
+    let mut file = BufWriter::new(File::create(path)?);
+    write!(file, "PF\n{width} {height}\n-1.0\n")?;
+    for j in 0..height as usize {
+        for i in 0..width as usize {
+            let pix = fb.mean(i, j);
+            for c in [pix.x, pix.y, pix.z] {
+                file.write_all(&(c as f32).to_le_bytes())?;
+            }
+        }
+    }
+    file.flush()?;
+    Ok(())
+}
+
+/// Writes a heatmap of `counts`, running from black for pixels with no samples through red and
+/// yellow to white for the pixels with the most.
+pub fn write_sample_map(counts: &Array2<u32>, path: &Path) -> ImageResult<()> {
+    let (width, height) = counts.dim();
+    let most = counts.iter().copied().max().unwrap_or(0).max(1);
+
+    let image = RgbImage::from_fn(width as u32, height as u32, |i, j| {
+        let t = 3.0 * f64::from(counts[(i as usize, height - 1 - j as usize)]) / f64::from(most);
+        let channel = |start: f64| ((t - start).clamp(0.0, 1.0) * 255.0).round() as u8;
+        Rgb([channel(0.0), channel(1.0), channel(2.0)])
+    });
+    image.save(path)
+}
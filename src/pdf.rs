@@ -0,0 +1,101 @@
+use cgmath::InnerSpace;
+use rand::{Rng, RngCore};
+
+use crate::{
+    hit::light::Light,
+    math::{
+        consts::{FRAC_1_PI, PI},
+        Float, Onb,
+    },
+    Vec3,
+};
+
+/// A distribution over directions from outside this crate, for custom materials to scatter with.
+pub trait CustomPdf {
+    /// Density over solid angle of `generate` picking `direction`.
+    fn value(&self, direction: Vec3) -> Float;
+
+    fn generate(&self, rng: &mut dyn RngCore) -> Vec3;
+}
+
+/// A distribution over directions, to pick the directions of rays from.
+pub enum Pdf<'a> {
+    /// Cosine-weighted over the hemisphere about the basis' `w`, as light leaves a Lambertian
+    /// surface.
+    Cosine(Onb),
+    /// Uniform over every direction.
+    Sphere,
+    /// Towards points chosen uniformly on `light`, as seen from `origin`.
+    Light {
+        light: &'a Light,
+        origin: Vec3,
+    },
+    /// Towards one of `lights`, each as likely to be picked as the others, as seen from `origin`.
+    /// The same as a `Mixture` of `Light`s with equal chances, without allocating one.
+    Lights {
+        lights: &'a [Light],
+        origin: Vec3,
+    },
+    /// Picks one of several distributions at random, each with the chance paired with it. The
+    /// chances add up to one.
+    Mixture(Vec<(Float, Pdf<'a>)>),
+    Custom(Box<dyn CustomPdf + 'a>),
+}
+
+impl Pdf<'_> {
+    /// Density over solid angle of `generate` picking `direction`.
+    pub fn value(&self, direction: Vec3) -> Float {
+        match self {
+            Pdf::Cosine(onb) => (onb.w.dot(direction.unit_vec().0) * FRAC_1_PI).max(0.0),
+            Pdf::Sphere => 0.25 / PI,
+            Pdf::Light { light, origin } => light.pdf_value(*origin, direction),
+            Pdf::Lights { lights, origin } => {
+                let chance = (lights.len() as Float).recip();
+                lights
+                    .iter()
+                    .map(|light| chance * light.pdf_value(*origin, direction))
+                    .sum()
+            }
+            Pdf::Mixture(pdfs) => pdfs
+                .iter()
+                .map(|(chance, pdf)| chance * pdf.value(direction))
+                .sum(),
+            Pdf::Custom(pdf) => pdf.value(direction),
+        }
+    }
+
+    pub fn generate(&self, rng: &mut impl Rng) -> Vec3 {
+        match self {
+            Pdf::Cosine(onb) => onb.local(Vec3::random_cosine_direction(rng)),
+            Pdf::Sphere => Vec3::random_unit_vector(rng),
+            Pdf::Light { light, origin } => light.random_towards(*origin, rng),
+            Pdf::Lights { lights, origin } => {
+                // Picks the light the way `Mixture` would, so both use the same random numbers
+                let chance = (lights.len() as Float).recip();
+                let mut x: Float = rng.gen();
+                let light = lights
+                    .iter()
+                    .find(|_| {
+                        let found = x < chance;
+                        x -= chance;
+                        found
+                    })
+                    // Only missed by rounding error
+                    .unwrap_or_else(|| lights.last().unwrap());
+                light.random_towards(*origin, rng)
+            }
+            Pdf::Mixture(pdfs) => {
+                let mut x: Float = rng.gen();
+                for (chance, pdf) in pdfs {
+                    if x < *chance {
+                        return pdf.generate(rng);
+                    }
+                    x -= chance;
+                }
+                // Only reachable by rounding error
+                pdfs.last().unwrap().1.generate(rng)
+            }
+            Pdf::Custom(pdf) => pdf.generate(rng),
+        }
+    }
+}
@@ -1,13 +1,13 @@
-use crate::Vec3;
+use crate::{math::Float, Vec3};
 
 pub struct Ray {
     pub origin: Vec3,
     pub direction: Vec3,
-    pub time: f64,
+    pub time: Float,
 }
 
 impl Ray {
-    pub fn at(&self, ray_parameter: f64) -> Vec3 {
+    pub fn at(&self, ray_parameter: Float) -> Vec3 {
         self.origin + ray_parameter * self.direction
     }
 }
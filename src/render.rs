@@ -0,0 +1,502 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use cgmath::{Vector3, Zero};
+use clap::ValueEnum;
+use ndarray::{s, Zip};
+use rand::Rng;
+
+// .into_par_iter()  is often commented out for profiling, so this will scream
+#[allow(unused_imports)]
+use rayon::prelude::*;
+
+use crate::{
+    adaptive::Adaptive,
+    camera::Camera,
+    hit::HitRecord,
+    mat::Scatter,
+    math::{wide, Float},
+    ray::Ray,
+    rng::{self, RenderRng},
+    scene::{RenderSettings, Scene},
+    tiles::{self, Tile, TileOrder},
+    wavefront::{self, Path},
+    Framebuffer, Vec3,
+};
+
+/// Renders a scene into framebuffers, one pass at a time.
+///
+/// Every pixel gets `settings.samples_per_pixel` samples, and then with adaptive sampling more
+/// while it's still too noisy. Each sample has its own random numbers, so a render comes out the
+/// same however it's split into passes and tiles, and on however many threads.
+pub struct Renderer<'a> {
+    scene: &'a Scene,
+    camera: Camera,
+    settings: RenderSettings,
+    seed: u64,
+    pass_samples: u32,
+    adaptive: Option<Adaptive>,
+    tiles: Vec<Tile>,
+    integrator: Integrator,
+}
+
+/// How the paths of a render are traced. They all give exactly the same image.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Integrator {
+    /// One path at a time, recursing at each bounce
+    Recursive,
+    /// Many paths at once, a stage of every bounce at a time
+    Wavefront,
+    /// As `wavefront`, grouping paths by the material they hit before scattering them
+    SortedWavefront,
+}
+
+/// How long a tile took to render in one pass.
+pub struct TileTime {
+    pub tile: Tile,
+    pub samples: u64,
+    pub time: Duration,
+}
+
+impl<'a> Renderer<'a> {
+    /// A renderer that takes every sample in one pass, in 32 pixel tiles spiralling out from the
+    /// middle of the image, tracing paths recursively.
+    pub fn new(scene: &'a Scene, settings: RenderSettings, seed: u64) -> Self {
+        let camera = scene.camera.build(settings.aspect_ratio());
+        let tiles = tiles::tiles(
+            settings.width as usize,
+            settings.height as usize,
+            32,
+            TileOrder::Spiral,
+        );
+        Self {
+            scene,
+            camera,
+            pass_samples: settings.samples_per_pixel.max(1) as u32,
+            settings,
+            seed,
+            adaptive: None,
+            tiles,
+            integrator: Integrator::Recursive,
+        }
+    }
+
+    /// Samples per pixel to add in each pass.
+    pub fn with_pass_samples(mut self, pass_samples: u32) -> Self {
+        self.pass_samples = pass_samples.max(1);
+        self
+    }
+
+    pub fn with_adaptive(mut self, adaptive: Adaptive) -> Self {
+        self.adaptive = Some(adaptive);
+        self
+    }
+
+    pub fn with_integrator(mut self, integrator: Integrator) -> Self {
+        self.integrator = integrator;
+        self
+    }
+
+    /// Splits the image into tiles of `size` by `size` pixels, handed out to threads in `order`.
+    pub fn with_tiles(mut self, size: usize, order: TileOrder) -> Self {
+        self.tiles = tiles::tiles(
+            self.settings.width as usize,
+            self.settings.height as usize,
+            size.max(1),
+            order,
+        );
+        self
+    }
+
+    pub fn settings(&self) -> &RenderSettings {
+        &self.settings
+    }
+
+    /// An empty framebuffer the size of the image.
+    pub fn framebuffer(&self) -> Framebuffer {
+        Framebuffer::new(self.settings.width, self.settings.height)
+    }
+
+    /// Renders every pass into a new framebuffer.
+    pub fn render(&self) -> Framebuffer {
+        let mut fb = self.framebuffer();
+        while !self.render_pass(&mut fb, |_| {}).is_empty() {}
+        fb
+    }
+
+    /// How many samples the next pass will add to `fb`.
+    pub fn planned(&self, fb: &Framebuffer) -> u64 {
+        Zip::from(&fb.sums)
+            .and(&fb.sum_squares)
+            .and(&fb.counts)
+            .fold(0, |total, &sum, &sum_squares, &count| {
+                let end = self.next_count(sum, sum_squares, count);
+                total + u64::from(end.saturating_sub(count))
+            })
+    }
+
+    /// Adds the next pass of samples to `fb`, which must be the size of the image, calling
+    /// `progress` with the number of samples taken as each pixel finishes. Tiles are handed out
+    /// to whichever thread is free, and written back to `fb` as they finish. Returns how long each
+    /// tile took, in the order the tiles are handed out, or nothing if the render is already
+    /// finished.
+    pub fn render_pass(
+        &self,
+        fb: &mut Framebuffer,
+        progress: impl Fn(u64) + Sync,
+    ) -> Vec<TileTime> {
+        if self.planned(fb) == 0 {
+            return Vec::new();
+        }
+
+        let fb = Mutex::new(fb);
+        let mut times: Vec<_> = self
+            .tiles
+            .iter()
+            .enumerate()
+            .par_bridge()
+            .map(|(index, tile)| (index, self.render_tile(&fb, *tile, &progress)))
+            .collect();
+
+        // Threads finish tiles in whatever order they like
+        times.sort_unstable_by_key(|&(index, _)| index);
+        times.into_iter().map(|(_, tile_time)| tile_time).collect()
+    }
+
+    /// How many samples a pixel should have by the end of the next pass, given its sum, sum of
+    /// squared luminance and sample count.
+    fn next_count(&self, sum: Vector3<f64>, sum_squares: f64, count: u32) -> u32 {
+        let target = self.settings.samples_per_pixel as u32;
+        if count < target {
+            (count + self.pass_samples).min(target)
+        } else {
+            match &self.adaptive {
+                Some(adaptive) if adaptive.needs_more(sum, sum_squares, count) => {
+                    (count + self.pass_samples).min(adaptive.max_samples)
+                }
+                _ => count,
+            }
+        }
+    }
+
+    /// Renders the next pass of one tile into a copy of its part of `fb`, and writes it back.
+    fn render_tile(
+        &self,
+        fb: &Mutex<&mut Framebuffer>,
+        tile: Tile,
+        progress: &impl Fn(u64),
+    ) -> TileTime {
+        let region = s![tile.x..tile.x + tile.width, tile.y..tile.y + tile.height];
+        let mut tile_fb = {
+            let fb = fb.lock().unwrap();
+            Framebuffer {
+                sums: fb.sums.slice(region).to_owned(),
+                sum_squares: fb.sum_squares.slice(region).to_owned(),
+                counts: fb.counts.slice(region).to_owned(),
+            }
+        };
+
+        let start = Instant::now();
+        let samples = match self.integrator {
+            Integrator::Recursive => self.trace_recursive(&mut tile_fb, tile, progress),
+            Integrator::Wavefront => self.trace_wavefront(&mut tile_fb, tile, false, progress),
+            Integrator::SortedWavefront => self.trace_wavefront(&mut tile_fb, tile, true, progress),
+        };
+
+        let time = start.elapsed();
+
+        let mut fb = fb.lock().unwrap();
+        fb.sums.slice_mut(region).assign(&tile_fb.sums);
+        fb.sum_squares
+            .slice_mut(region)
+            .assign(&tile_fb.sum_squares);
+        fb.counts.slice_mut(region).assign(&tile_fb.counts);
+
+        TileTime {
+            tile,
+            samples,
+            time,
+        }
+    }
+
+    /// Adds the next pass of samples to `tile_fb`, which holds `tile`, tracing each path in turn.
+    /// Returns the number of samples taken.
+    fn trace_recursive(
+        &self,
+        tile_fb: &mut Framebuffer,
+        tile: Tile,
+        progress: &impl Fn(u64),
+    ) -> u64 {
+        let mut samples = 0;
+        Zip::indexed(&mut tile_fb.sums)
+            .and(&mut tile_fb.sum_squares)
+            .and(&mut tile_fb.counts)
+            .for_each(|(i, j), pixel, sum_squares, count| {
+                let (x, y) = (tile.x + i, tile.y + j);
+                let end = self.next_count(*pixel, *sum_squares, *count);
+                for sample in *count..end {
+                    let (r, mut rng) = self.camera_ray(x, y, sample);
+                    let colour =
+                        ray_colour(&r, self.scene, self.settings.max_depth, None, &mut rng);
+                    *pixel += colour.wide();
+                    *sum_squares += wide(colour.luminance()).powi(2);
+                }
+                if end > *count {
+                    let taken = u64::from(end - *count);
+                    samples += taken;
+                    progress(taken);
+                    *count = end;
+                }
+            });
+        samples
+    }
+
+    /// As `trace_recursive`, but tracing up to `wavefront::QUEUE_SIZE` paths together. The
+    /// samples are added to each pixel in the same order as `trace_recursive` adds them, so the
+    /// sums come out the same.
+    fn trace_wavefront(
+        &self,
+        tile_fb: &mut Framebuffer,
+        tile: Tile,
+        sort: bool,
+        progress: &impl Fn(u64),
+    ) -> u64 {
+        let mut queued = Vec::new();
+        Zip::indexed(&tile_fb.sums)
+            .and(&tile_fb.sum_squares)
+            .and(&tile_fb.counts)
+            .for_each(|(i, j), &pixel, &sum_squares, &count| {
+                let end = self.next_count(pixel, sum_squares, count);
+                queued.extend((count..end).map(|sample| ((i, j), sample)));
+            });
+
+        for chunk in queued.chunks(wavefront::QUEUE_SIZE) {
+            let mut paths: Vec<_> = chunk
+                .iter()
+                .map(|&((i, j), sample)| {
+                    let (r, rng) = self.camera_ray(tile.x + i, tile.y + j, sample);
+                    Path::new(r, rng, self.settings.max_depth)
+                })
+                .collect();
+            wavefront::trace(self.scene, &mut paths, sort);
+
+            for (&(pixel, sample), path) in chunk.iter().zip(&paths) {
+                let colour = path.radiance().unwrap();
+                tile_fb.sums[pixel] += colour.wide();
+                tile_fb.sum_squares[pixel] += wide(colour.luminance()).powi(2);
+                tile_fb.counts[pixel] = sample + 1;
+            }
+            progress(chunk.len() as u64);
+        }
+        queued.len() as u64
+    }
+
+    /// The camera ray for one sample of the pixel at `(x, y)`, and the random numbers for the rest
+    /// of its path.
+    fn camera_ray(&self, x: usize, y: usize, sample: u32) -> (Ray, RenderRng) {
+        let RenderSettings { width, height, .. } = self.settings;
+        let pixel_index = y as u64 * width as u64 + x as u64;
+        let mut rng = rng::sample_rng(self.seed, pixel_index, sample as u64);
+        let vals: [Float; 2] = rng.gen();
+        let u = (x as Float + vals[0]) / (width as Float - 1.0);
+        let v = (y as Float + vals[1]) / (height as Float - 1.0);
+        (self.camera.get_ray(u, v, &mut rng), rng)
+    }
+}
+
+/// The radiance arriving back along `ray`, following it for at most `depth` bounces.
+///
+/// `bsdf_pdf` is the density the previous bounce picked `ray`'s direction with, or `None` if light
+/// sampling couldn't have picked it, as for camera rays and specular bounces.
+pub fn ray_colour(
+    ray: &Ray,
+    scene: &Scene,
+    depth: i32,
+    bsdf_pdf: Option<Float>,
+    rng: &mut impl Rng,
+) -> Vec3 {
+    if depth == 0 {
+        return Vec3::zero();
+    }
+    let Some(hit) = scene.world.hit(ray, 0.001, Float::INFINITY, rng) else {
+        return scene.background.colour(ray);
+    };
+
+    let material = &scene.materials[hit.material];
+    let mut colour = emitted(ray, &hit, bsdf_pdf, scene);
+
+    match material.sample(ray, &hit, rng) {
+        None => colour,
+        Some(Scatter::Specular {
+            attenuation,
+            ray: scattered,
+        }) => colour + attenuation * ray_colour(&scattered, scene, depth - 1, None, rng),
+        Some(Scatter::Diffuse(pdf)) => {
+            if !scene.lights.is_empty() {
+                colour += sample_light(ray, &hit, scene, rng);
+            }
+
+            let direction = pdf.generate(rng);
+            let pdf = pdf.value(direction);
+            if pdf <= 0.0 {
+                return colour;
+            }
+            let scattered = Ray {
+                origin: hit.p,
+                direction,
+                time: ray.time,
+            };
+            let f = material.eval(&hit, direction);
+            colour + f / pdf * ray_colour(&scattered, scene, depth - 1, Some(pdf), rng)
+        }
+    }
+}
+
+/// Light given off at `hit` back along `ray`, which arrived with `bsdf_pdf` as for `ray_colour`.
+pub(crate) fn emitted(ray: &Ray, hit: &HitRecord, bsdf_pdf: Option<Float>, scene: &Scene) -> Vec3 {
+    let mut colour = scene.materials[hit.material].emitted(hit);
+    // Light sampling at the last bounce could have found this light too
+    if let Some(bsdf_pdf) = bsdf_pdf {
+        if !colour.is_zero() && !scene.lights.is_empty() {
+            let light_pdf = scene.lights.pdf(ray.origin).value(ray.direction);
+            colour *= power_heuristic(bsdf_pdf, light_pdf);
+        }
+    }
+    colour
+}
+
+/// Light arriving at `hit` directly from a randomly chosen point on one of the scene's lights,
+/// weighted against the chance of the material's own sampling finding it.
+fn sample_light(ray: &Ray, hit: &HitRecord, scene: &Scene, rng: &mut impl Rng) -> Vec3 {
+    match ShadowRay::new(ray, hit, scene, rng) {
+        Some(shadow) => {
+            let light = scene.world.hit(&shadow.ray, 0.001, Float::INFINITY, rng);
+            shadow.light(hit, light, scene)
+        }
+        None => Vec3::zero(),
+    }
+}
+
+/// A ray from a hit towards a randomly chosen point on one of the scene's lights, traced to find
+/// out whether the light can be seen from there.
+pub(crate) struct ShadowRay {
+    pub ray: Ray,
+    /// The hit material's BSDF for the ray's direction.
+    f: Vec3,
+    light_pdf: Float,
+}
+
+impl ShadowRay {
+    /// `None` if the light can't contribute anything along the chosen direction, so that there's
+    /// no need to trace it.
+    pub fn new(ray: &Ray, hit: &HitRecord, scene: &Scene, rng: &mut impl Rng) -> Option<Self> {
+        let pdf = scene.lights.pdf(hit.p);
+        let direction = pdf.generate(rng);
+        let light_pdf = pdf.value(direction);
+        let f = scene.materials[hit.material].eval(hit, direction);
+        if light_pdf <= 0.0 || f.is_zero() {
+            return None;
+        }
+
+        Some(Self {
+            ray: Ray {
+                origin: hit.p,
+                direction,
+                time: ray.time,
+            },
+            f,
+            light_pdf,
+        })
+    }
+
+    /// The light arriving at `hit` along the ray, given what the ray hit.
+    pub fn light(&self, hit: &HitRecord, light: Option<HitRecord>, scene: &Scene) -> Vec3 {
+        match light {
+            Some(light) => {
+                let material = &scene.materials[hit.material];
+                let weight = power_heuristic(self.light_pdf, material.pdf(hit, self.ray.direction));
+                scene.materials[light.material].emitted(&light) * self.f * (weight / self.light_pdf)
+            }
+            None => Vec3::zero(),
+        }
+    }
+}
+
+/// Veach's power heuristic, with an exponent of 2, for the weight of a sample taken with density
+/// `pdf` when `other_pdf` could also have produced it.
+#[inline]
+fn power_heuristic(pdf: Float, other_pdf: Float) -> Float {
+    let (a, b) = (pdf * pdf, other_pdf * other_pdf);
+    a / (a + b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene;
+
+    /// A small render of `name`, big enough to fill more than one wavefront queue.
+    fn render(name: &str, integrator: Integrator) -> Framebuffer {
+        let (scene, mut settings) = scene::load(name, &mut rng::scene_rng(1)).unwrap();
+        settings.width = 24;
+        settings.height = 24;
+        settings.samples_per_pixel = 8;
+        Renderer::new(&scene, settings, 1)
+            .with_integrator(integrator)
+            .render()
+    }
+
+    #[test]
+    fn same_however_split() {
+        let (scene, _) = scene::load("cornell", &mut rng::scene_rng(1)).unwrap();
+        let renderer = || {
+            let settings = RenderSettings {
+                width: 24,
+                height: 24,
+                samples_per_pixel: 8,
+                max_depth: 50,
+            };
+            Renderer::new(&scene, settings, 1)
+        };
+        let in_threads = |threads: usize, renderer: Renderer| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+                .install(|| renderer.render())
+        };
+
+        let expected = in_threads(1, renderer());
+        for fb in [
+            in_threads(3, renderer()),
+            in_threads(3, renderer().with_pass_samples(3)),
+            in_threads(2, renderer().with_tiles(5, TileOrder::Hilbert)),
+            in_threads(
+                4,
+                renderer()
+                    .with_tiles(7, TileOrder::Scanline)
+                    .with_pass_samples(1),
+            ),
+        ] {
+            assert_eq!(fb.sums, expected.sums);
+            assert_eq!(fb.sum_squares, expected.sum_squares);
+            assert_eq!(fb.counts, expected.counts);
+        }
+    }
+
+    #[test]
+    fn integrators_agree() {
+        // Between them, metal, glass, lights and a medium
+        for name in ["random", "cornell", "cornell-smoke"] {
+            let recursive = render(name, Integrator::Recursive);
+            for integrator in [Integrator::Wavefront, Integrator::SortedWavefront] {
+                let fb = render(name, integrator);
+                assert_eq!(fb.sums, recursive.sums, "{name}");
+                assert_eq!(fb.sum_squares, recursive.sum_squares, "{name}");
+                assert_eq!(fb.counts, recursive.counts, "{name}");
+            }
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use rand::SeedableRng;
+use rand_pcg::Pcg64Mcg;
+
+/// The random number generator used for everything, from building scenes to sampling paths.
+pub type RenderRng = Pcg64Mcg;
+
+pub fn scene_rng(seed: u64) -> RenderRng {
+    RenderRng::seed_from_u64(seed)
+}
+
+/// An independent stream of random numbers for one sample of one pixel. Because every sample gets
+/// its own stream, an image comes out the same no matter how pixels are divided between threads.
+#[inline]
+pub fn sample_rng(seed: u64, pixel: u64, sample: u64) -> RenderRng {
+    let key = splitmix64(seed ^ splitmix64(pixel ^ splitmix64(sample)));
+    let state = (u128::from(splitmix64(key)) << 64) | u128::from(splitmix64(!key));
+    RenderRng::new(state)
+}
+
+/// The finaliser from SplitMix64, which thoroughly mixes the bits of its input.
+#[inline]
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
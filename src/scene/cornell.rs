@@ -0,0 +1,159 @@
+use std::sync::Arc;
+
+use cgmath::Deg;
+
+use crate::{
+    camera::CameraSettings,
+    hit::{
+        constant_medium::ConstantMedium,
+        cuboid::Cuboid,
+        list::HittableList,
+        rect::{Plane, Rect},
+        transform::Transform,
+        Hittable,
+    },
+    mat::{
+        diffuse_light::DiffuseLight, isotropic::Isotropic, lambertian::Lambertian, Material,
+        MaterialId, Materials,
+    },
+    math::Float,
+    tex::Texture,
+    Vec3,
+};
+
+use super::{Background, RenderSettings, Scene};
+
+/// The Cornell box, lit only by the lamp in its ceiling.
+pub fn cornell_box() -> (Scene, RenderSettings) {
+    let mut materials = Materials::new();
+    let white = lambertian(&mut materials, 0.73, 0.73, 0.73);
+    let light = diffuse_light(&mut materials, 15.0);
+
+    let mut world = walls(&mut materials, white, light, (213.0, 227.0), (343.0, 332.0));
+    world.0.extend([
+        Hittable::Transform(Box::new(tall_box(white))),
+        Hittable::Transform(Box::new(short_box(white))),
+    ]);
+
+    scene(world, materials)
+}
+
+/// The Cornell box with its two blocks replaced by blocks of smoke and fog, under a larger, dimmer
+/// lamp.
+pub fn cornell_smoke() -> (Scene, RenderSettings) {
+    let mut materials = Materials::new();
+    let white = lambertian(&mut materials, 0.73, 0.73, 0.73);
+    let light = diffuse_light(&mut materials, 7.0);
+    let mut isotropic = |c| {
+        materials.add(Material::Isotropic(Isotropic {
+            albedo: Texture::Solid(Vec3::new(c, c, c)),
+        }))
+    };
+    let smoke = isotropic(0.0);
+    let fog = isotropic(1.0);
+    let medium = |boundary, phase_function| {
+        Hittable::ConstantMedium(ConstantMedium::new(
+            Arc::new(Hittable::Transform(Box::new(boundary))),
+            0.01,
+            phase_function,
+        ))
+    };
+
+    // The boundaries' material is never seen
+    let mut world = walls(&mut materials, white, light, (113.0, 127.0), (443.0, 432.0));
+    world.0.extend([
+        medium(tall_box(white), smoke),
+        medium(short_box(white), fog),
+    ]);
+
+    scene(world, materials)
+}
+
+fn lambertian(materials: &mut Materials, r: Float, g: Float, b: Float) -> MaterialId {
+    materials.add(Material::Lambertian(Lambertian {
+        albedo: Texture::Solid(Vec3::new(r, g, b)),
+    }))
+}
+
+fn diffuse_light(materials: &mut Materials, brightness: Float) -> MaterialId {
+    materials.add(Material::DiffuseLight(DiffuseLight {
+        emit: Texture::Solid(Vec3::new(brightness, brightness, brightness)),
+    }))
+}
+
+/// The five walls of the box, with a lamp in the ceiling between `light_min` and `light_max`.
+fn walls(
+    materials: &mut Materials,
+    white: MaterialId,
+    light: MaterialId,
+    light_min: (Float, Float),
+    light_max: (Float, Float),
+) -> HittableList {
+    let red = lambertian(materials, 0.65, 0.05, 0.05);
+    let green = lambertian(materials, 0.12, 0.45, 0.15);
+
+    let rect = |plane, min, max, k, material| {
+        Hittable::Rect(Rect {
+            plane,
+            min,
+            max,
+            k,
+            material,
+        })
+    };
+
+    HittableList(vec![
+        rect(Plane::Yz, (0.0, 0.0), (555.0, 555.0), 555.0, green),
+        rect(Plane::Yz, (0.0, 0.0), (555.0, 555.0), 0.0, red),
+        rect(Plane::Xz, light_min, light_max, 554.0, light),
+        rect(Plane::Xz, (0.0, 0.0), (555.0, 555.0), 0.0, white),
+        rect(Plane::Xz, (0.0, 0.0), (555.0, 555.0), 555.0, white),
+        rect(Plane::Xy, (0.0, 0.0), (555.0, 555.0), 555.0, white),
+    ])
+}
+
+fn tall_box(material: MaterialId) -> Transform {
+    Transform::new(Arc::new(Hittable::Cuboid(Box::new(Cuboid::new(
+        Vec3::zero(),
+        Vec3::newi(165, 330, 165),
+        material,
+    )))))
+    .rotated_euler(Deg(0.0), Deg(15.0), Deg(0.0))
+    .translated(Vec3::newi(265, 0, 295))
+}
+
+fn short_box(material: MaterialId) -> Transform {
+    Transform::new(Arc::new(Hittable::Cuboid(Box::new(Cuboid::new(
+        Vec3::zero(),
+        Vec3::newi(165, 165, 165),
+        material,
+    )))))
+    .rotated_euler(Deg(0.0), Deg(-18.0), Deg(0.0))
+    .translated(Vec3::newi(130, 0, 65))
+}
+
+fn scene(world: HittableList, materials: Materials) -> (Scene, RenderSettings) {
+    let scene = Scene::new(
+        world,
+        materials,
+        Background::BLACK,
+        CameraSettings {
+            position: Vec3::newi(278, 278, -800),
+            target: Vec3::newi(278, 278, 0),
+            vert: Vec3::UNIT_UP,
+            vfov: Deg(40.0),
+            aperture: 0.0,
+            focus_dist: 10.0,
+            shutter_open_time: 0.0,
+            shutter_close_time: 1.0,
+        },
+    );
+    let settings = RenderSettings {
+        width: 600,
+        height: 600,
+        samples_per_pixel: 200,
+        ..Default::default()
+    };
+
+    (scene, settings)
+}
@@ -0,0 +1,623 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use cgmath::{Deg, Matrix, Matrix4, Quaternion};
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{
+    camera::CameraSettings,
+    hit::{
+        bvh::Bvh,
+        constant_medium::ConstantMedium,
+        cuboid::Cuboid,
+        list::HittableList,
+        moving_sphere::MovingSphere,
+        quad::Quad,
+        rect::{Plane, Rect},
+        sphere::Sphere,
+        transform::Transform,
+        Hittable,
+    },
+    mat::{
+        dielectric::Dielectric, diffuse_light::DiffuseLight, isotropic::Isotropic,
+        lambertian::Lambertian, metal::Metal, Material, MaterialId, Materials,
+    },
+    math::Float,
+    obj,
+    tex::{
+        checker::Checker,
+        image_texture::ImageTexture,
+        noise::{Noise, Pattern},
+        Texture,
+    },
+    Vec3,
+};
+
+use super::{Background, RenderSettings, Scene};
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(PathBuf, std::io::Error),
+    Parse(toml::de::Error),
+    UnknownMaterial(String),
+    Mesh(PathBuf, Box<obj::ObjError>),
+    Image(PathBuf, image::ImageError),
+    SingularTransform,
+    MediumWithoutMaterial,
+    ShutterClosesBeforeOpening(Float, Float),
+    TooSmall(u32, u32),
+    NegativeDepth(i32),
+}
+
+impl Display for SceneError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SceneError::Io(path, e) => write!(f, "couldn't read {}: {e}", path.display()),
+            SceneError::Parse(e) => write!(f, "invalid scene file: {e}"),
+            SceneError::UnknownMaterial(name) => write!(f, "no material named `{name}`"),
+            SceneError::Mesh(path, e) => write!(f, "couldn't load {}: {e}", path.display()),
+            SceneError::Image(path, e) => write!(f, "couldn't load {}: {e}", path.display()),
+            SceneError::SingularTransform => write!(f, "transform squashes an object flat"),
+            SceneError::MediumWithoutMaterial => {
+                write!(
+                    f,
+                    "mesh filled with a medium needs a `material` to scatter light"
+                )
+            }
+            SceneError::ShutterClosesBeforeOpening(open, close) => write!(
+                f,
+                "camera shutter closes at {close}, before it opens at {open}"
+            ),
+            SceneError::TooSmall(width, height) => write!(
+                f,
+                "image is {width}x{height}, but must be at least 2 pixels each way"
+            ),
+            SceneError::NegativeDepth(depth) => {
+                write!(f, "maximum depth is {depth}, but can't be negative")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+type Colour = [Float; 3];
+
+fn vec3(c: Colour) -> Vec3 {
+    Vec3::new(c[0], c[1], c[2])
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneFile {
+    #[serde(default)]
+    render: RenderDesc,
+    camera: CameraDesc,
+    #[serde(default)]
+    background: BackgroundDesc,
+    #[serde(default)]
+    materials: BTreeMap<String, MaterialDesc>,
+    #[serde(default)]
+    objects: Vec<ObjectEntry>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct RenderDesc {
+    width: Option<u32>,
+    height: Option<u32>,
+    samples_per_pixel: Option<usize>,
+    max_depth: Option<i32>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDesc {
+    position: Colour,
+    target: Colour,
+    #[serde(default = "default_up")]
+    up: Colour,
+    vfov: Float,
+    #[serde(default)]
+    aperture: Float,
+    /// Defaults to the distance to `target`.
+    focus_distance: Option<Float>,
+    #[serde(default)]
+    shutter_open: Float,
+    #[serde(default)]
+    shutter_close: Float,
+}
+
+fn default_up() -> Colour {
+    [0.0, 1.0, 0.0]
+}
+
+#[derive(Deserialize, Default)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum BackgroundDesc {
+    #[default]
+    Sky,
+    Black,
+    Solid {
+        colour: Colour,
+    },
+    Gradient {
+        bottom: Colour,
+        top: Colour,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum MaterialDesc {
+    Lambertian {
+        albedo: TextureDesc,
+    },
+    Metal {
+        albedo: TextureDesc,
+        fuzz: Float,
+    },
+    Dielectric {
+        ir: Float,
+    },
+    DiffuseLight {
+        emit: TextureDesc,
+    },
+    /// Scatters equally in every direction, for use inside media.
+    Isotropic {
+        albedo: TextureDesc,
+    },
+}
+
+/// Either a plain colour, or a table describing a texture.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TextureDesc {
+    Solid(Colour),
+    Pattern(PatternDesc),
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum PatternDesc {
+    Checker {
+        odd: Box<TextureDesc>,
+        even: Box<TextureDesc>,
+        size: Float,
+    },
+    Image {
+        path: PathBuf,
+    },
+    Noise {
+        pattern: Pattern,
+        scale: Float,
+        low: Colour,
+        high: Colour,
+    },
+}
+
+#[derive(Deserialize)]
+struct ObjectEntry {
+    #[serde(flatten)]
+    object: ObjectDesc,
+    transform: Option<TransformDesc>,
+    /// Fills the object with smoke or fog of this density instead of giving it a surface. The
+    /// object must be closed and convex, and its material, usually `isotropic`, is what scatters
+    /// light inside it.
+    density: Option<Float>,
+}
+
+/// Moves an object from where it's described to where it should be. Each part is optional.
+/// Whatever order they're written in the file, they're always applied as `matrix`, then `scale`,
+/// then `rotate`, then `quaternion`, then `translate`, so that an object is scaled and rotated
+/// about its own origin before being moved into place.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TransformDesc {
+    /// An arbitrary affine transform, as four rows.
+    matrix: Option<[[Float; 4]; 4]>,
+    scale: Option<Colour>,
+    /// Degrees about the x axis, then the y axis, then the z axis.
+    rotate: Option<Colour>,
+    /// `[w, x, y, z]`.
+    quaternion: Option<[Float; 4]>,
+    translate: Option<Colour>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum ObjectDesc {
+    Sphere {
+        centre: Colour,
+        radius: Float,
+        material: String,
+    },
+    MovingSphere {
+        centre_start: Colour,
+        centre_end: Colour,
+        time_start: Float,
+        time_end: Float,
+        radius: Float,
+        material: String,
+    },
+    /// An axis-aligned rectangle at `k` along the axis not in `plane`.
+    Rect {
+        plane: Plane,
+        min: [Float; 2],
+        max: [Float; 2],
+        k: Float,
+        material: String,
+    },
+    /// A parallelogram with one corner at `corner` and sides `u` and `v`.
+    Quad {
+        corner: Colour,
+        u: Colour,
+        v: Colour,
+        material: String,
+    },
+    /// An axis-aligned box.
+    Box {
+        min: Colour,
+        max: Colour,
+        material: String,
+    },
+    /// A Wavefront OBJ file. Unless `material` is given, faces use the materials from the
+    /// model's own MTL library.
+    Mesh {
+        path: PathBuf,
+        #[serde(default = "default_scale")]
+        scale: Float,
+        #[serde(default)]
+        offset: Colour,
+        material: Option<String>,
+    },
+}
+
+impl ObjectDesc {
+    fn material_name(&self) -> Option<&str> {
+        match self {
+            ObjectDesc::Sphere { material, .. }
+            | ObjectDesc::MovingSphere { material, .. }
+            | ObjectDesc::Rect { material, .. }
+            | ObjectDesc::Quad { material, .. }
+            | ObjectDesc::Box { material, .. } => Some(material),
+            ObjectDesc::Mesh { material, .. } => material.as_deref(),
+        }
+    }
+}
+
+fn default_scale() -> Float {
+    1.0
+}
+
+/// Loads a TOML scene description. Relative paths to textures and meshes are resolved against
+/// the directory the scene file is in. Render settings missing from the file are left at their
+/// defaults. `rng` is used for anything random in the scene, such as procedural textures.
+pub fn load(
+    path: impl AsRef<Path>,
+    rng: &mut impl Rng,
+) -> Result<(Scene, RenderSettings), SceneError> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path).map_err(|e| SceneError::Io(path.into(), e))?;
+    let file: SceneFile = toml::from_str(&text).map_err(SceneError::Parse)?;
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let defaults = RenderSettings::default();
+    let settings = RenderSettings {
+        width: file.render.width.unwrap_or(defaults.width),
+        height: file.render.height.unwrap_or(defaults.height),
+        samples_per_pixel: file
+            .render
+            .samples_per_pixel
+            .unwrap_or(defaults.samples_per_pixel),
+        max_depth: file.render.max_depth.unwrap_or(defaults.max_depth),
+    };
+    // Pixel coordinates are divided by one less than the size
+    if settings.width < 2 || settings.height < 2 {
+        return Err(SceneError::TooSmall(settings.width, settings.height));
+    }
+    // Paths only stop bouncing when they count down to zero
+    if settings.max_depth < 0 {
+        return Err(SceneError::NegativeDepth(settings.max_depth));
+    }
+
+    let mut materials = Materials::new();
+    let names = file
+        .materials
+        .into_iter()
+        .map(|(name, desc)| Ok((name, materials.add(build_material(desc, base_dir, rng)?))))
+        .collect::<Result<BTreeMap<_, _>, SceneError>>()?;
+
+    let camera = file.camera;
+    if camera.shutter_close < camera.shutter_open {
+        return Err(SceneError::ShutterClosesBeforeOpening(
+            camera.shutter_open,
+            camera.shutter_close,
+        ));
+    }
+    let camera = CameraSettings {
+        position: vec3(camera.position),
+        target: vec3(camera.target),
+        vert: vec3(camera.up),
+        vfov: Deg(camera.vfov),
+        aperture: camera.aperture,
+        focus_dist: camera
+            .focus_distance
+            .unwrap_or_else(|| (vec3(camera.position) - vec3(camera.target)).length()),
+        shutter_open_time: camera.shutter_open,
+        shutter_close_time: camera.shutter_close,
+    };
+
+    let mut objects = HittableList(Vec::new());
+    for entry in file.objects {
+        // A medium scatters light with the object's own material
+        let medium = match (entry.density, entry.object.material_name()) {
+            (None, _) => None,
+            (Some(density), Some(name)) => Some((density, material(&names, name)?)),
+            (Some(_), None) => return Err(SceneError::MediumWithoutMaterial),
+        };
+
+        let mut parts = build_object(entry.object, base_dir, &names, &mut materials)?;
+        if entry.transform.is_none() && medium.is_none() {
+            objects.0.extend(parts);
+            continue;
+        }
+
+        // Meshes get their own hierarchy, so that they can be transformed or filled as a whole
+        let mut object = if parts.len() == 1 {
+            parts.pop().unwrap()
+        } else {
+            Hittable::Bvh(Bvh::new(
+                HittableList(parts),
+                camera.shutter_open_time,
+                camera.shutter_close_time,
+            ))
+        };
+        if let Some(desc) = entry.transform {
+            let transform =
+                build_transform(desc, Arc::new(object)).ok_or(SceneError::SingularTransform)?;
+            object = Hittable::Transform(Box::new(transform));
+        }
+        // Filled after transforming, so that density is per unit of distance in the world
+        if let Some((density, phase_function)) = medium {
+            object = Hittable::ConstantMedium(ConstantMedium::new(
+                Arc::new(object),
+                density,
+                phase_function,
+            ));
+        }
+        objects.0.push(object);
+    }
+
+    let background = match file.background {
+        BackgroundDesc::Sky => Background::SKY,
+        BackgroundDesc::Black => Background::BLACK,
+        BackgroundDesc::Solid { colour } => Background::Solid(vec3(colour)),
+        BackgroundDesc::Gradient { bottom, top } => Background::Gradient {
+            bottom: vec3(bottom),
+            top: vec3(top),
+        },
+    };
+
+    Ok((Scene::new(objects, materials, background, camera), settings))
+}
+
+/// `names` maps the names of the file's materials to where they are in `materials`, which any
+/// materials from meshes' own MTL libraries are added to.
+fn build_object(
+    desc: ObjectDesc,
+    base_dir: &Path,
+    names: &BTreeMap<String, MaterialId>,
+    materials: &mut Materials,
+) -> Result<Vec<Hittable>, SceneError> {
+    Ok(match desc {
+        ObjectDesc::Sphere {
+            centre,
+            radius,
+            material: name,
+        } => vec![Hittable::Sphere(Sphere {
+            centre: vec3(centre),
+            radius,
+            material: material(names, &name)?,
+        })],
+        ObjectDesc::MovingSphere {
+            centre_start,
+            centre_end,
+            time_start,
+            time_end,
+            radius,
+            material: name,
+        } => vec![Hittable::MovingSphere(MovingSphere {
+            centre_start: vec3(centre_start),
+            centre_end: vec3(centre_end),
+            time_start,
+            time_end,
+            radius,
+            material: material(names, &name)?,
+        })],
+        ObjectDesc::Rect {
+            plane,
+            min,
+            max,
+            k,
+            material: name,
+        } => vec![Hittable::Rect(Rect::new(
+            plane,
+            (min[0], min[1]),
+            (max[0], max[1]),
+            k,
+            material(names, &name)?,
+        ))],
+        ObjectDesc::Quad {
+            corner,
+            u,
+            v,
+            material: name,
+        } => vec![Hittable::Quad(Quad::new(
+            vec3(corner),
+            vec3(u),
+            vec3(v),
+            material(names, &name)?,
+        ))],
+        ObjectDesc::Box {
+            min,
+            max,
+            material: name,
+        } => vec![Hittable::Cuboid(Box::new(Cuboid::new(
+            vec3(min),
+            vec3(max),
+            material(names, &name)?,
+        )))],
+        ObjectDesc::Mesh {
+            path,
+            scale,
+            offset,
+            material: name,
+        } => {
+            let path = base_dir.join(path);
+            let mut model = obj::load_obj(&path, scale, vec3(offset), materials)
+                .map_err(|e| SceneError::Mesh(path, Box::new(e)))?;
+            if let Some(name) = name {
+                let material = material(names, &name)?;
+                for obj in &mut model.0 {
+                    if let Hittable::Triangle(triangle) = obj {
+                        triangle.material = material;
+                    }
+                }
+            }
+            model.0
+        }
+    })
+}
+
+fn material(names: &BTreeMap<String, MaterialId>, name: &str) -> Result<MaterialId, SceneError> {
+    names
+        .get(name)
+        .copied()
+        .ok_or_else(|| SceneError::UnknownMaterial(name.to_owned()))
+}
+
+/// Applies the parts of `desc` in the fixed order `TransformDesc` gives, whatever order they're
+/// written in.
+fn build_transform(desc: TransformDesc, object: Arc<Hittable>) -> Option<Transform> {
+    let mut transform = match desc.matrix {
+        // Rows in the file, but cgmath matrices are column-major
+        Some(m) => Transform::from_matrix(object, Matrix4::from(m).transpose())?,
+        None => Transform::new(object),
+    };
+    if let Some(scale) = desc.scale {
+        transform = transform.scaled(vec3(scale))?;
+    }
+    if let Some([x, y, z]) = desc.rotate {
+        transform = transform.rotated_euler(Deg(x), Deg(y), Deg(z));
+    }
+    if let Some([w, x, y, z]) = desc.quaternion {
+        transform = transform.rotated(Quaternion::new(w, x, y, z));
+    }
+    if let Some(offset) = desc.translate {
+        transform = transform.translated(vec3(offset));
+    }
+    Some(transform)
+}
+
+fn build_material(
+    desc: MaterialDesc,
+    base_dir: &Path,
+    rng: &mut impl Rng,
+) -> Result<Material, SceneError> {
+    Ok(match desc {
+        MaterialDesc::Lambertian { albedo } => Material::Lambertian(Lambertian {
+            albedo: build_texture(albedo, base_dir, rng)?,
+        }),
+        MaterialDesc::Metal { albedo, fuzz } => Material::Metal(Metal {
+            albedo: build_texture(albedo, base_dir, rng)?,
+            fuzz,
+        }),
+        MaterialDesc::Dielectric { ir } => Material::Dielectric(Dielectric { ir }),
+        MaterialDesc::DiffuseLight { emit } => Material::DiffuseLight(DiffuseLight {
+            emit: build_texture(emit, base_dir, rng)?,
+        }),
+        MaterialDesc::Isotropic { albedo } => Material::Isotropic(Isotropic {
+            albedo: build_texture(albedo, base_dir, rng)?,
+        }),
+    })
+}
+
+fn build_texture(
+    desc: TextureDesc,
+    base_dir: &Path,
+    rng: &mut impl Rng,
+) -> Result<Texture, SceneError> {
+    Ok(match desc {
+        TextureDesc::Solid(colour) => Texture::Solid(vec3(colour)),
+        TextureDesc::Pattern(PatternDesc::Checker { odd, even, size }) => {
+            Texture::Checker(Arc::new(Checker {
+                odd: build_texture(*odd, base_dir, rng)?,
+                even: build_texture(*even, base_dir, rng)?,
+                size,
+            }))
+        }
+        TextureDesc::Pattern(PatternDesc::Image { path }) => {
+            let path = base_dir.join(path);
+            match ImageTexture::open(&path) {
+                Ok(image) => Texture::Image(Arc::new(image)),
+                Err(e) => return Err(SceneError::Image(path, e)),
+            }
+        }
+        TextureDesc::Pattern(PatternDesc::Noise {
+            pattern,
+            scale,
+            low,
+            high,
+        }) => Texture::Noise(Arc::new(Noise::new(
+            rng,
+            pattern,
+            scale,
+            vec3(low),
+            vec3(high),
+        ))),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{hit::Hittable, mat::Materials};
+
+    #[test]
+    fn transform_parts_apply_in_a_fixed_order() {
+        // Written in the opposite order to the one they're applied in
+        let desc: TransformDesc = toml::from_str(
+            "translate = [0, 1, 0]
+             quaternion = [0.7071067811865476, 0.7071067811865476, 0, 0]
+             rotate = [0, 0, 90]
+             scale = [2, 1, 1]
+             matrix = [[1, 0, 0, 1], [0, 1, 0, 0], [0, 0, 1, 0], [0, 0, 0, 1]]",
+        )
+        .unwrap();
+        let material = Materials::new().add(Material::Lambertian(Lambertian {
+            albedo: Texture::Solid(Vec3::zero()),
+        }));
+        let sphere = Arc::new(Hittable::Sphere(Sphere {
+            centre: Vec3::new(1.0, 0.0, 0.0),
+            radius: 0.5,
+            material,
+        }));
+
+        // (1, 0, 0) is moved to (2, 0, 0) by the matrix, scaled to (4, 0, 0), turned about z to
+        // (0, 4, 0) and then about x to (0, 0, 4), and finally moved up to (0, 1, 4)
+        let centre = build_transform(desc, sphere)
+            .unwrap()
+            .bounding_box(0.0, 1.0)
+            .centroid();
+        assert!(
+            (centre - Vec3::new(0.0, 1.0, 4.0)).length() < 1e-4,
+            "{centre}"
+        );
+    }
+}
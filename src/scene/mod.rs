@@ -0,0 +1,141 @@
+pub mod cornell;
+pub mod file;
+pub mod random;
+
+use rand::Rng;
+
+use crate::{
+    camera::CameraSettings,
+    hit::{bvh::Bvh, light::Lights, list::HittableList, sphere_batch},
+    mat::Materials,
+    math::Float,
+    ray::Ray,
+    Error, Result, Vec3,
+};
+
+pub struct Scene {
+    pub world: Bvh,
+    /// Everything in `world` refers to its material in here.
+    pub materials: Materials,
+    /// Emitters in `world` to aim shadow rays at.
+    pub lights: Lights,
+    pub background: Background,
+    pub camera: CameraSettings,
+}
+
+/// Names of the scenes built into the program.
+pub const BUILTIN_SCENES: [&str; 3] = ["random", "cornell", "cornell-smoke"];
+
+/// Looks up one of the scenes built into the program by name.
+pub fn builtin(name: &str, rng: &mut impl Rng) -> Option<(Scene, RenderSettings)> {
+    match name {
+        "random" => Some((random::random_scene(rng), RenderSettings::default())),
+        "cornell" => Some(cornell::cornell_box()),
+        "cornell-smoke" => Some(cornell::cornell_smoke()),
+        _ => None,
+    }
+}
+
+/// Looks up a built-in scene by name, or failing that loads the scene file at that path.
+pub fn load(name: &str, rng: &mut impl Rng) -> Result<(Scene, RenderSettings)> {
+    match builtin(name, rng) {
+        Some(builtin) => Ok(builtin),
+        None => file::load(name, rng).map_err(|e| Error::Scene(name.to_owned(), e)),
+    }
+}
+
+/// A fingerprint of the scene `load` would load for `name`, to tell whether it's changed: a hash
+/// of the scene file's contents, or for a built-in scene of its name. Meshes and images that a
+/// scene file refers to aren't included.
+pub fn fingerprint(name: &str) -> Result<u64> {
+    if BUILTIN_SCENES.contains(&name) {
+        return Ok(fnv1a(name.as_bytes()));
+    }
+    std::fs::read(name)
+        .map(|bytes| fnv1a(&bytes))
+        .map_err(|e| Error::Scene(name.to_owned(), file::SceneError::Io(name.into(), e)))
+}
+
+/// 64-bit FNV-1a, which unlike `std`'s hashers is the same on every version and platform.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+impl Scene {
+    pub fn new(
+        objects: HittableList,
+        materials: Materials,
+        background: Background,
+        camera: CameraSettings,
+    ) -> Self {
+        let lights = Lights::find(&objects.0, &materials);
+        let objects = sphere_batch::pack(objects);
+        // Moving objects are bounded over the whole time the shutter is open
+        let world = Bvh::new(objects, camera.shutter_open_time, camera.shutter_close_time);
+        Self {
+            world,
+            materials,
+            lights,
+            background,
+            camera,
+        }
+    }
+}
+
+pub struct RenderSettings {
+    pub width: u32,
+    pub height: u32,
+    pub samples_per_pixel: usize,
+    pub max_depth: i32,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            width: 1920,
+            height: 1080,
+            samples_per_pixel: 100,
+            max_depth: 50,
+        }
+    }
+}
+
+impl RenderSettings {
+    pub fn aspect_ratio(&self) -> Float {
+        self.width as Float / self.height as Float
+    }
+}
+
+/// The radiance arriving along rays that escape the scene.
+pub enum Background {
+    Solid(Vec3),
+    /// Blends from `bottom` for rays pointing straight down to `top` for rays pointing straight
+    /// up.
+    Gradient {
+        bottom: Vec3,
+        top: Vec3,
+    },
+}
+
+impl Background {
+    pub const BLACK: Background = Background::Solid(Vec3::zero());
+
+    pub const SKY: Background = Background::Gradient {
+        bottom: Vec3::one(),
+        top: Vec3::new(0.4, 0.7, 1.0),
+    };
+
+    #[inline]
+    pub fn colour(&self, ray: &Ray) -> Vec3 {
+        match self {
+            Background::Solid(colour) => *colour,
+            Background::Gradient { bottom, top } => {
+                let unit_dir = ray.direction.unit_vec();
+                let t = 0.5 * (unit_dir.y + 1.0);
+                (1.0 - t) * bottom + t * top
+            }
+        }
+    }
+}
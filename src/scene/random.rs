@@ -0,0 +1,149 @@
+use std::sync::Arc;
+
+use cgmath::Deg;
+use rand::Rng;
+
+use crate::{
+    camera::CameraSettings,
+    hit::{list::HittableList, moving_sphere::MovingSphere, sphere::Sphere, Hittable},
+    mat::{dielectric::Dielectric, lambertian::Lambertian, metal::Metal, Material, Materials},
+    math::Float,
+    tex::{
+        checker::Checker,
+        noise::{Noise, Pattern},
+        Texture,
+    },
+    Vec3,
+};
+
+use super::{Background, Scene};
+
+pub fn random_scene(rng: &mut impl Rng) -> Scene {
+    let mut world = HittableList(Vec::new());
+    let mut materials = Materials::new();
+
+    const GLASS_MATERIAL: Dielectric = Dielectric { ir: 1.5 };
+    const SPHERE_3_MATERIAL: Metal = Metal {
+        albedo: Texture::Solid(Vec3::new(0.7, 0.6, 0.5)),
+        fuzz: 0.0,
+    };
+
+    let ground_material = Lambertian {
+        albedo: Texture::Checker(Arc::new(Checker {
+            odd: Texture::Solid(Vec3::new(0.2, 0.3, 0.1)),
+            even: Texture::Noise(Arc::new(Noise::new(
+                rng,
+                Pattern::Marble,
+                4.0,
+                Vec3::new(0.3, 0.3, 0.35),
+                Vec3::new(0.9, 0.9, 0.9),
+            ))),
+            size: 0.5,
+        })),
+    };
+    let sphere_2_material = Lambertian {
+        albedo: Texture::Noise(Arc::new(Noise::new(
+            rng,
+            Pattern::Wood,
+            4.0,
+            Vec3::new(0.25, 0.12, 0.05),
+            Vec3::new(0.5, 0.3, 0.15),
+        ))),
+    };
+
+    // All the glass spheres share one material
+    let glass = materials.add(Material::Dielectric(GLASS_MATERIAL));
+
+    world.0.push(Hittable::Sphere(Sphere {
+        centre: Vec3::newi(0, -1000, 0),
+        radius: 1000.0,
+        material: materials.add(Material::Lambertian(ground_material)),
+    }));
+
+    for a in -11..11 {
+        for b in -11..11 {
+            let a = a as Float;
+            let b = b as Float;
+            let choose_mat: Float = rng.gen();
+            let centre = Vec3::new(
+                a + 0.9 * rng.gen::<Float>(),
+                0.2,
+                b + 0.9 * rng.gen::<Float>(),
+            );
+            let radius = 0.2;
+
+            if (centre - Vec3::new(4.0, 0.2, 0.0)).length() > 0.9 {
+                if choose_mat < 0.8 {
+                    //diffuse
+                    let albedo = Vec3::random(rng) * Vec3::random(rng);
+                    let sphere_material = Lambertian {
+                        albedo: Texture::Solid(albedo),
+                    };
+                    let centre_2 = centre + Vec3::new(0.0, rng.gen_range(0.0..0.5), 0.0);
+                    world.0.push(Hittable::MovingSphere(MovingSphere {
+                        centre_start: centre,
+                        centre_end: centre_2,
+                        radius,
+                        material: materials.add(Material::Lambertian(sphere_material)),
+                        time_start: 0.0,
+                        time_end: 1.0,
+                    }))
+                } else if choose_mat < 0.95 {
+                    //metal
+                    let albedo = Vec3::random_with_range(rng, 0.5, 1.0);
+                    let fuzz = rng.gen_range(0.0..0.5);
+                    let sphere_material = Metal {
+                        albedo: Texture::Solid(albedo),
+                        fuzz,
+                    };
+                    world.0.push(Hittable::Sphere(Sphere {
+                        centre,
+                        radius,
+                        material: materials.add(Material::Metal(sphere_material)),
+                    }))
+                } else {
+                    //glass
+                    world.0.push(Hittable::Sphere(Sphere {
+                        centre,
+                        radius,
+                        material: glass,
+                    }))
+                }
+            }
+        }
+    }
+
+    world.0.push(Hittable::Sphere(Sphere {
+        centre: Vec3::newi(0, 1, 0),
+        radius: 1.0,
+        material: glass,
+    }));
+
+    world.0.push(Hittable::Sphere(Sphere {
+        centre: Vec3::newi(-4, 1, 0),
+        radius: 1.0,
+        material: materials.add(Material::Lambertian(sphere_2_material)),
+    }));
+
+    world.0.push(Hittable::Sphere(Sphere {
+        centre: Vec3::newi(4, 1, 0),
+        radius: 1.0,
+        material: materials.add(Material::Metal(SPHERE_3_MATERIAL)),
+    }));
+
+    Scene::new(
+        world,
+        materials,
+        Background::SKY,
+        CameraSettings {
+            position: Vec3::newi(13, 2, 3),
+            target: Vec3::newi(0, 0, 0),
+            vert: Vec3::UNIT_UP,
+            vfov: Deg(20.0),
+            aperture: 0.1,
+            focus_dist: 10.0,
+            shutter_open_time: 0.0,
+            shutter_close_time: 1.0,
+        },
+    )
+}
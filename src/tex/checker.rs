@@ -0,0 +1,27 @@
+use crate::{
+    math::{consts, Float},
+    Vec3,
+};
+
+use super::Texture;
+
+/// A solid 3D checkerboard, alternating between `odd` and `even` every `size` units along each
+/// axis.
+pub struct Checker {
+    pub odd: Texture,
+    pub even: Texture,
+    pub size: Float,
+}
+
+impl Checker {
+    #[inline]
+    pub fn value(&self, u: Float, v: Float, p: &Vec3) -> Vec3 {
+        let k = consts::PI / self.size;
+        let sines = (k * p.x).sin() * (k * p.y).sin() * (k * p.z).sin();
+        if sines.is_sign_negative() {
+            self.odd.value(u, v, p)
+        } else {
+            self.even.value(u, v, p)
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use std::path::Path;
+
+use image::{ImageResult, RgbImage};
+
+use crate::{math::Float, tonemap::srgb_decode, Vec3};
+
+pub struct ImageTexture {
+    image: RgbImage,
+}
+
+impl ImageTexture {
+    pub fn open(path: impl AsRef<Path>) -> ImageResult<Self> {
+        Ok(Self {
+            image: image::open(path)?.into_rgb8(),
+        })
+    }
+
+    #[inline]
+    pub fn value(&self, u: Float, v: Float) -> Vec3 {
+        // Texture coordinates wrap, and v runs up the image rather than down
+        let u = u.rem_euclid(1.0);
+        let v = 1.0 - v.rem_euclid(1.0);
+
+        let i = ((u * self.image.width() as Float) as u32).min(self.image.width() - 1);
+        let j = ((v * self.image.height() as Float) as u32).min(self.image.height() - 1);
+        let [r, g, b] = self.image.get_pixel(i, j).0;
+
+        // Images are stored in sRGB, but we render in linear space
+        let decode = |c: u8| srgb_decode(f64::from(c) / 255.0) as Float;
+        Vec3::new(decode(r), decode(g), decode(b))
+    }
+}
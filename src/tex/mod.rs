@@ -0,0 +1,31 @@
+pub mod checker;
+pub mod image_texture;
+pub mod noise;
+pub mod perlin;
+
+use std::sync::Arc;
+
+use crate::{math::Float, Vec3};
+
+use self::{checker::Checker, image_texture::ImageTexture, noise::Noise};
+
+#[derive(Clone)]
+pub enum Texture {
+    Solid(Vec3),
+    Checker(Arc<Checker>),
+    Image(Arc<ImageTexture>),
+    Noise(Arc<Noise>),
+}
+
+impl Texture {
+    /// Colour at surface coordinates `(u, v)`, which is the point `p` in space.
+    #[inline]
+    pub fn value(&self, u: Float, v: Float, p: &Vec3) -> Vec3 {
+        match self {
+            Texture::Solid(colour) => *colour,
+            Texture::Checker(c) => c.value(u, v, p),
+            Texture::Image(i) => i.value(u, v),
+            Texture::Noise(n) => n.value(p),
+        }
+    }
+}
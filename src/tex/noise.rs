@@ -0,0 +1,61 @@
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::{math::Float, Vec3};
+
+use super::perlin::Perlin;
+
+const TURBULENCE_DEPTH: usize = 7;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Pattern {
+    /// Plain Perlin noise.
+    Smooth,
+    Turbulence,
+    /// Veins running across the z axis, perturbed by turbulence.
+    Marble,
+    /// Rings around the y axis, perturbed by turbulence.
+    Wood,
+}
+
+/// A solid procedural texture, blending between `low` and `high` according to `pattern`.
+/// `scale` is the frequency of the pattern.
+pub struct Noise {
+    pub perlin: Perlin,
+    pub pattern: Pattern,
+    pub scale: Float,
+    pub low: Vec3,
+    pub high: Vec3,
+}
+
+impl Noise {
+    pub fn new(rng: &mut impl Rng, pattern: Pattern, scale: Float, low: Vec3, high: Vec3) -> Self {
+        Self {
+            perlin: Perlin::new(rng),
+            pattern,
+            scale,
+            low,
+            high,
+        }
+    }
+
+    #[inline]
+    pub fn value(&self, p: &Vec3) -> Vec3 {
+        let sp = self.scale * p;
+        let t = match self.pattern {
+            Pattern::Smooth => 0.5 * (1.0 + self.perlin.noise(&sp)),
+            Pattern::Turbulence => self.perlin.turbulence(&sp, TURBULENCE_DEPTH).min(1.0),
+            Pattern::Marble => {
+                let phase = sp.z + 10.0 * self.perlin.turbulence(p, TURBULENCE_DEPTH);
+                0.5 * (1.0 + phase.sin())
+            }
+            Pattern::Wood => {
+                let radius = (sp.x.powi(2) + sp.z.powi(2)).sqrt();
+                let rings = radius + 2.0 * self.perlin.turbulence(p, TURBULENCE_DEPTH);
+                rings.fract()
+            }
+        };
+        (1.0 - t) * self.low + t * self.high
+    }
+}
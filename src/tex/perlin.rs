@@ -0,0 +1,82 @@
+use cgmath::InnerSpace;
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{math::Float, Vec3};
+
+const POINT_COUNT: usize = 256;
+
+/// Gradient noise, with a random unit vector at every integer lattice point.
+pub struct Perlin {
+    gradients: Vec<Vec3>,
+    perm_x: Vec<usize>,
+    perm_y: Vec<usize>,
+    perm_z: Vec<usize>,
+}
+
+impl Perlin {
+    pub fn new(rng: &mut impl Rng) -> Self {
+        Self {
+            gradients: (0..POINT_COUNT)
+                .map(|_| Vec3::random_unit_vector(rng))
+                .collect(),
+            perm_x: permutation(rng),
+            perm_y: permutation(rng),
+            perm_z: permutation(rng),
+        }
+    }
+
+    /// Noise at `p`, in the range `-1..=1`.
+    pub fn noise(&self, p: &Vec3) -> Float {
+        let (i, j, k) = (p.x.floor(), p.y.floor(), p.z.floor());
+        let (u, v, w) = (p.x - i, p.y - j, p.z - k);
+        let (i, j, k) = (i as i64, j as i64, k as i64);
+
+        let wrap = |n: i64| (n & (POINT_COUNT as i64 - 1)) as usize;
+
+        // Hermite smoothing, so that the lattice doesn't show through as creases
+        let uu = u * u * (3.0 - 2.0 * u);
+        let vv = v * v * (3.0 - 2.0 * v);
+        let ww = w * w * (3.0 - 2.0 * w);
+
+        let mut accum = 0.0;
+        for di in 0..2 {
+            for dj in 0..2 {
+                for dk in 0..2 {
+                    let gradient = self.gradients[self.perm_x[wrap(i + di)]
+                        ^ self.perm_y[wrap(j + dj)]
+                        ^ self.perm_z[wrap(k + dk)]];
+                    let (fi, fj, fk) = (di as Float, dj as Float, dk as Float);
+                    let weight = Vec3::new(u - fi, v - fj, w - fk);
+
+                    accum += (fi * uu + (1.0 - fi) * (1.0 - uu))
+                        * (fj * vv + (1.0 - fj) * (1.0 - vv))
+                        * (fk * ww + (1.0 - fk) * (1.0 - ww))
+                        * gradient.dot(weight.0);
+                }
+            }
+        }
+        accum
+    }
+
+    /// Sum of `depth` octaves of noise, each at twice the frequency and half the amplitude of the
+    /// last.
+    pub fn turbulence(&self, p: &Vec3, depth: usize) -> Float {
+        let mut accum = 0.0;
+        let mut p = *p;
+        let mut weight = 1.0;
+
+        for _ in 0..depth {
+            accum += weight * self.noise(&p);
+            weight *= 0.5;
+            p *= 2.0;
+        }
+
+        accum.abs()
+    }
+}
+
+fn permutation(rng: &mut impl Rng) -> Vec<usize> {
+    let mut p: Vec<usize> = (0..POINT_COUNT).collect();
+    p.shuffle(rng);
+    p
+}
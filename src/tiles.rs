@@ -0,0 +1,127 @@
+use clap::ValueEnum;
+
+/// The order tiles are handed out to render threads in.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum TileOrder {
+    /// Outwards from the middle of the image, where the subject usually is
+    Spiral,
+    /// Along a Hilbert curve, so that tiles rendered one after another are neighbours
+    Hilbert,
+    /// Row by row from the top of the image
+    Scanline,
+}
+
+/// A block of pixels, `width` by `height` with its corner at `(x, y)`. y runs up the image.
+#[derive(Clone, Copy)]
+pub struct Tile {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// Splits an image into tiles of `size` by `size` pixels, smaller along the right and top edges
+/// if the image doesn't divide evenly, and puts them in `order`.
+pub fn tiles(width: usize, height: usize, size: usize, order: TileOrder) -> Vec<Tile> {
+    let columns = width.div_ceil(size);
+    let rows = height.div_ceil(size);
+
+    let grid: Vec<(usize, usize)> = match order {
+        TileOrder::Spiral => spiral(columns, rows),
+        TileOrder::Hilbert => hilbert(columns, rows),
+        TileOrder::Scanline => (0..rows)
+            .rev()
+            .flat_map(|row| (0..columns).map(move |column| (column, row)))
+            .collect(),
+    };
+
+    grid.into_iter()
+        .map(|(column, row)| {
+            let x = column * size;
+            let y = row * size;
+            Tile {
+                x,
+                y,
+                width: size.min(width - x),
+                height: size.min(height - y),
+            }
+        })
+        .collect()
+}
+
+/// Rings of tiles around the middle one, each going round anticlockwise from the right.
+fn spiral(columns: usize, rows: usize) -> Vec<(usize, usize)> {
+    let centre = ((columns as f64 - 1.0) / 2.0, (rows as f64 - 1.0) / 2.0);
+    let mut grid: Vec<_> = (0..rows)
+        .flat_map(|row| (0..columns).map(move |column| (column, row)))
+        .collect();
+    let key = |&(column, row): &(usize, usize)| {
+        let dx = column as f64 - centre.0;
+        let dy = row as f64 - centre.1;
+        let ring = dx.abs().max(dy.abs()).round();
+        let angle = dy.atan2(dx).rem_euclid(std::f64::consts::TAU);
+        (ring, angle)
+    };
+    grid.sort_by(|a, b| key(a).partial_cmp(&key(b)).unwrap());
+    grid
+}
+
+/// Tiles along the Hilbert curve filling the smallest power of two square that covers the grid,
+/// skipping the parts of the square outside it.
+fn hilbert(columns: usize, rows: usize) -> Vec<(usize, usize)> {
+    let n = columns.max(rows).next_power_of_two();
+    (0..n * n)
+        .map(|d| hilbert_point(n, d))
+        .filter(|&(column, row)| column < columns && row < rows)
+        .collect()
+}
+
+/// The point `d` along the Hilbert curve through an `n` by `n` grid.
+fn hilbert_point(n: usize, d: usize) -> (usize, usize) {
+    let (mut x, mut y) = (0, 0);
+    let mut t = d;
+    let mut s = 1;
+    while s < n {
+        let rx = 1 & (t / 2);
+        let ry = 1 & (t ^ rx);
+        // Rotate the quadrant so the curve joins up with the next one
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - x;
+                y = s - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        x += s * rx;
+        y += s * ry;
+        t /= 4;
+        s *= 2;
+    }
+    (x, y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_pixel_in_one_tile() {
+        for order in [TileOrder::Spiral, TileOrder::Hilbert, TileOrder::Scanline] {
+            for (width, height, size) in [(64, 64, 32), (100, 37, 7), (5, 300, 16), (2, 2, 32)] {
+                let mut covered = vec![0; width * height];
+                for tile in tiles(width, height, size, order) {
+                    assert!(tile.width > 0 && tile.height > 0);
+                    for y in tile.y..tile.y + tile.height {
+                        for x in tile.x..tile.x + tile.width {
+                            covered[y * width + x] += 1;
+                        }
+                    }
+                }
+                assert!(
+                    covered.iter().all(|&c| c == 1),
+                    "{width}x{height} by {size}"
+                );
+            }
+        }
+    }
+}
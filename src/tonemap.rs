@@ -0,0 +1,100 @@
+use clap::ValueEnum;
+use image::Rgb;
+
+use cgmath::Vector3;
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Operator {
+    /// No tone mapping; anything brighter than white is clipped
+    Clamp,
+    /// x / (1 + x), which never quite reaches white
+    Reinhard,
+    /// Reinhard, rescaled so that the white point maps to white
+    ExtendedReinhard,
+    /// Narkowicz's fit of the ACES filmic curve
+    Aces,
+    /// John Hable's filmic curve from Uncharted 2
+    Hable,
+}
+
+/// Turns linear radiance into displayable 8-bit sRGB.
+pub struct ToneMapping {
+    pub operator: Operator,
+    /// Stops to brighten the image by before mapping. Negative values darken it.
+    pub exposure: f64,
+    /// The smallest radiance that maps to white, for operators that have such a thing.
+    pub white_point: f64,
+}
+
+impl Default for ToneMapping {
+    /// Clipping, with no change of exposure.
+    fn default() -> Self {
+        Self {
+            operator: Operator::Clamp,
+            exposure: 0.0,
+            white_point: 4.0,
+        }
+    }
+}
+
+impl ToneMapping {
+    #[inline]
+    fn map_channel(&self, c: f64) -> f64 {
+        let c = c * self.exposure.exp2();
+        match self.operator {
+            Operator::Clamp => c,
+            Operator::Reinhard => c / (1.0 + c),
+            Operator::ExtendedReinhard => c * (1.0 + c / self.white_point.powi(2)) / (1.0 + c),
+            Operator::Aces => (c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14),
+            Operator::Hable => {
+                const EXPOSURE_BIAS: f64 = 2.0;
+                hable_partial(EXPOSURE_BIAS * c) / hable_partial(self.white_point)
+            }
+        }
+    }
+
+    /// `radiance` is the sum of `num_samples` samples.
+    #[inline]
+    pub fn to_pixel(&self, radiance: Vector3<f64>, num_samples: usize) -> Rgb<u8> {
+        let scale = 1.0 / num_samples as f64;
+        let encode = |c: f64| {
+            let mapped = self.map_channel(c * scale);
+            // Also catches NaNs, which clamp leaves alone
+            let mapped = if mapped.is_nan() { 0.0 } else { mapped };
+            (srgb_encode(mapped.clamp(0.0, 1.0)) * 255.0).round() as u8
+        };
+
+        Rgb([encode(radiance.x), encode(radiance.y), encode(radiance.z)])
+    }
+}
+
+#[inline]
+fn hable_partial(x: f64) -> f64 {
+    const A: f64 = 0.15;
+    const B: f64 = 0.50;
+    const C: f64 = 0.10;
+    const D: f64 = 0.20;
+    const E: f64 = 0.02;
+    const F: f64 = 0.30;
+    ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F
+}
+
+/// The sRGB transfer function, from linear intensity to encoded value, both in `0..=1`.
+#[inline]
+pub fn srgb_encode(linear: f64) -> f64 {
+    if linear <= 0.003_130_8 {
+        12.92 * linear
+    } else {
+        1.055 * linear.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// The inverse of [`srgb_encode`].
+#[inline]
+pub fn srgb_decode(encoded: f64) -> f64 {
+    if encoded <= 0.040_45 {
+        encoded / 12.92
+    } else {
+        ((encoded + 0.055) / 1.055).powf(2.4)
+    }
+}
@@ -0,0 +1,239 @@
+use crate::{
+    hit::HitRecord,
+    mat::{Material, Scatter},
+    math::Float,
+    pdf::Pdf,
+    ray::Ray,
+    render::{emitted, ShadowRay},
+    rng::RenderRng,
+    scene::Scene,
+    Vec3,
+};
+
+/// Most paths traced together at once. Bigger queues give each stage longer runs of similar work,
+/// but each path in flight keeps room for a vertex per bounce, a few kilobytes at the default
+/// maximum depth.
+pub const QUEUE_SIZE: usize = 1 << 12;
+
+/// One sample's path through a scene, traced a bounce at a time alongside many others.
+pub struct Path {
+    ray: Ray,
+    rng: RenderRng,
+    /// Bounces left before the path is cut off.
+    depth: i32,
+    /// As for `ray_colour`.
+    bsdf_pdf: Option<Float>,
+    /// For each bounce so far, the light given off there and how much of the light arriving from
+    /// the next bounce gets through.
+    vertices: Vec<(Vec3, Vec3)>,
+    state: State,
+}
+
+enum State {
+    /// Waiting for `ray` to be intersected with the scene.
+    Tracing,
+    /// Waiting for the material at `hit` to scatter the ray.
+    Hit(HitRecord),
+    /// Scattered in a direction to be drawn from `pdf`, once the shadow ray, if there is one, has
+    /// been traced. `colour` is the light given off at `hit`, so far.
+    Diffuse {
+        hit: HitRecord,
+        colour: Vec3,
+        pdf: Pdf<'static>,
+        shadow: Option<ShadowRay>,
+    },
+    /// Ended, with the light arriving back from the end of the path.
+    Done(Vec3),
+}
+
+impl Path {
+    /// A path starting along `ray` that bounces at most `depth` times, drawing its random numbers
+    /// from `rng`.
+    pub fn new(ray: Ray, rng: RenderRng, depth: i32) -> Self {
+        Self {
+            ray,
+            rng,
+            depth,
+            bsdf_pdf: None,
+            vertices: Vec::with_capacity(depth.max(0) as usize),
+            state: State::Tracing,
+        }
+    }
+
+    /// The radiance arriving back along the path's first ray, once it has been traced.
+    ///
+    /// The bounces are added up from the far end, in the same order as the recursion in
+    /// `ray_colour`, so the result is exactly what that would have given.
+    pub fn radiance(&self) -> Option<Vec3> {
+        let State::Done(last) = self.state else {
+            return None;
+        };
+        Some(
+            self.vertices
+                .iter()
+                .rev()
+                .fold(last, |light, &(colour, attenuation)| {
+                    colour + attenuation * light
+                }),
+        )
+    }
+
+    /// Sends the path on along `ray`, recording the light `colour` given off where it bounced and
+    /// the `attenuation` of the light arriving back along `ray`.
+    fn bounce(&mut self, colour: Vec3, attenuation: Vec3, ray: Ray, bsdf_pdf: Option<Float>) {
+        self.vertices.push((colour, attenuation));
+        self.ray = ray;
+        self.bsdf_pdf = bsdf_pdf;
+        self.depth -= 1;
+        self.state = State::Tracing;
+    }
+}
+
+/// Traces every path to the end, a stage at a time over all the paths still going: intersecting
+/// them with the scene, scattering them off whatever they hit, tracing their shadow rays, and
+/// picking their next directions. With `sort`, paths are grouped by the kind of material they hit
+/// and the octant they arrived from before being scattered, so that runs of paths take the same
+/// branches through the material code.
+///
+/// Each path uses its own random numbers in the same order as `ray_colour` would, so the paths
+/// come out the same as they would traced one at a time, whatever order they're worked through in.
+pub fn trace(scene: &Scene, paths: &mut [Path], sort: bool) {
+    let mut active: Vec<usize> = (0..paths.len()).collect();
+    while !active.is_empty() {
+        intersect(scene, paths, &active);
+        active.retain(|&i| matches!(paths[i].state, State::Hit(_)));
+
+        if sort {
+            active.sort_by_cached_key(|&i| match &paths[i].state {
+                State::Hit(hit) => (
+                    material_kind(&scene.materials[hit.material]),
+                    octant(paths[i].ray.direction),
+                ),
+                _ => unreachable!(),
+            });
+        }
+
+        scatter(scene, paths, &active);
+        trace_shadows(scene, paths, &active);
+        choose_directions(scene, paths, &active);
+        active.retain(|&i| matches!(paths[i].state, State::Tracing));
+    }
+}
+
+/// Finds what each path's ray hits, ending those that miss everything or have run out of bounces.
+fn intersect(scene: &Scene, paths: &mut [Path], active: &[usize]) {
+    for &i in active {
+        let path = &mut paths[i];
+        path.state = if path.depth == 0 {
+            State::Done(Vec3::zero())
+        } else {
+            match scene
+                .world
+                .hit(&path.ray, 0.001, Float::INFINITY, &mut path.rng)
+            {
+                Some(hit) => State::Hit(hit),
+                None => State::Done(scene.background.colour(&path.ray)),
+            }
+        };
+    }
+}
+
+/// Scatters each path off the material it hit. Specular bounces go straight on to the next
+/// intersection, and diffuse ones pick where to send a shadow ray.
+fn scatter(scene: &Scene, paths: &mut [Path], active: &[usize]) {
+    for &i in active {
+        let path = &mut paths[i];
+        let State::Hit(hit) = std::mem::replace(&mut path.state, State::Tracing) else {
+            unreachable!()
+        };
+        let colour = emitted(&path.ray, &hit, path.bsdf_pdf, scene);
+
+        match scene.materials[hit.material].sample(&path.ray, &hit, &mut path.rng) {
+            None => path.state = State::Done(colour),
+            Some(Scatter::Specular { attenuation, ray }) => {
+                path.bounce(colour, attenuation, ray, None)
+            }
+            Some(Scatter::Diffuse(pdf)) => {
+                let shadow = if scene.lights.is_empty() {
+                    None
+                } else {
+                    ShadowRay::new(&path.ray, &hit, scene, &mut path.rng)
+                };
+                path.state = State::Diffuse {
+                    hit,
+                    colour,
+                    pdf,
+                    shadow,
+                };
+            }
+        }
+    }
+}
+
+/// Adds the light each shadow ray finds to the light given off where it started.
+fn trace_shadows(scene: &Scene, paths: &mut [Path], active: &[usize]) {
+    for &i in active {
+        let path = &mut paths[i];
+        if let State::Diffuse {
+            hit,
+            colour,
+            shadow: Some(shadow),
+            ..
+        } = &mut path.state
+        {
+            let light = scene
+                .world
+                .hit(&shadow.ray, 0.001, Float::INFINITY, &mut path.rng);
+            *colour += shadow.light(hit, light, scene);
+        }
+    }
+}
+
+/// Picks the next direction of each diffusely scattered path.
+fn choose_directions(scene: &Scene, paths: &mut [Path], active: &[usize]) {
+    for &i in active {
+        let path = &mut paths[i];
+        if !matches!(path.state, State::Diffuse { .. }) {
+            continue;
+        }
+        let State::Diffuse {
+            hit, colour, pdf, ..
+        } = std::mem::replace(&mut path.state, State::Tracing)
+        else {
+            unreachable!()
+        };
+
+        let direction = pdf.generate(&mut path.rng);
+        let pdf = pdf.value(direction);
+        if pdf <= 0.0 {
+            path.state = State::Done(colour);
+            continue;
+        }
+        let ray = Ray {
+            origin: hit.p,
+            direction,
+            time: path.ray.time,
+        };
+        let f = scene.materials[hit.material].eval(&hit, direction);
+        path.bounce(colour, f / pdf, ray, Some(pdf));
+    }
+}
+
+/// Which variant a material is, to group paths by.
+fn material_kind(material: &Material) -> u8 {
+    match material {
+        Material::Lambertian(_) => 0,
+        Material::Metal(_) => 1,
+        Material::Dielectric(_) => 2,
+        Material::DiffuseLight(_) => 3,
+        Material::Isotropic(_) => 4,
+        Material::Custom(_) => 5,
+    }
+}
+
+/// Which of the eight octants `direction` points into.
+fn octant(direction: Vec3) -> u8 {
+    u8::from(direction.x.is_sign_negative())
+        | u8::from(direction.y.is_sign_negative()) << 1
+        | u8::from(direction.z.is_sign_negative()) << 2
+}